@@ -31,11 +31,20 @@ pub fn derive_schema(input: TokenStream) -> TokenStream {
     let vis = input.vis;
 
     let output = match input.data {
-        Data::Struct(data) => match data.fields {
-            Fields::Named(fields) => derive_struct_named(vis, name, fields),
-            Fields::Unnamed(fields) => derive_struct_unnamed(vis, name, fields),
-            Fields::Unit => derive_struct_unit(vis, name),
-        },
+        Data::Struct(data) => {
+            let builder = derive_struct_builder(&vis, &name, &data.fields);
+
+            let output = match data.fields {
+                Fields::Named(fields) => derive_struct_named(vis, name, fields),
+                Fields::Unnamed(fields) => derive_struct_unnamed(vis, name, fields),
+                Fields::Unit => derive_struct_unit(vis, name),
+            };
+
+            quote! {
+                #builder
+                #output
+            }
+        }
         Data::Enum(data) => derive_enum(vis, name, data),
         Data::Union(_) => panic!("Cannot derive Schema for union"),
     };
@@ -43,6 +52,77 @@ pub fn derive_schema(input: TokenStream) -> TokenStream {
     TokenStream::from(output)
 }
 
+/// Expression of a struct whose fields are given by expressions, named
+/// `{Struct}Builder`. Unit structs have no builder since their value is
+/// already known.
+fn derive_struct_builder(
+    vis: &Visibility,
+    name: &Ident,
+    fields: &Fields,
+) -> proc_macro2::TokenStream {
+    if fields.is_empty() {
+        return quote! {};
+    }
+
+    let builder_name = format_ident!("{name}Builder");
+    let field_count = fields.len() as u32;
+    let field_types = fields.iter().map(|field| &field.ty).collect::<Vec<_>>();
+    let field_generics = (0..fields.len())
+        .map(|index| format_ident!("__Field{index}"))
+        .collect::<Vec<_>>();
+    let field_indexes = (0..fields.len()).map(|index| Index {
+        index: index as u32,
+        span: Span::call_site(),
+    });
+
+    let definition = match fields {
+        Fields::Named(fields) => {
+            let field_names = fields.named.iter().map(|field| &field.ident);
+
+            quote! {
+                {
+                    #(pub #field_names: #field_generics,)*
+                }
+            }
+        }
+        Fields::Unnamed(_) | Fields::Unit => quote! {
+            (#(pub #field_generics,)*);
+        },
+    };
+    let field_accesses = match fields {
+        Fields::Named(fields) => fields
+            .named
+            .iter()
+            .map(|field| field.ident.to_token_stream())
+            .collect::<Vec<_>>(),
+        Fields::Unnamed(_) | Fields::Unit => field_indexes.map(|index| quote! { #index }).collect(),
+    };
+
+    let doc = format!("Expression of a [`{name}`] built from expressions of its fields.");
+
+    quote! {
+        #[doc = #doc]
+        #vis struct #builder_name<#(#field_generics: ::database::Expression<Target = #field_types>,)*> #definition
+
+        impl<#(#field_generics: ::database::Expression<Target = #field_types>,)*> ::database::Expression for #builder_name<#(#field_generics,)*> {
+            type Target = #name;
+
+            fn write(
+                self,
+                write: &mut (impl ::database::__internal::tokio::io::AsyncWriteExt + ::std::marker::Unpin + ::std::marker::Send),
+            ) -> impl ::std::future::Future<Output = ::std::io::Result<()>> {
+                async move {
+                    write.write_u8(::database::expression_discriminant::PRODUCT).await?;
+                    write.write_u32(#field_count).await?;
+                    #(::std::boxed::Box::pin(::database::Expression::write(self.#field_accesses, write)).await?;)*
+
+                    Ok(())
+                }
+            }
+        }
+    }
+}
+
 fn derive_struct_named(
     vis: Visibility,
     name: Ident,
@@ -368,9 +448,195 @@ fn derive_struct_unit(vis: Visibility, name: Ident) -> proc_macro2::TokenStream
     }
 }
 
+/// Name of a closure parameter for a variant, `SomeVariant` gives
+/// `some_variant`.
+fn snake_case(ident: &Ident) -> Ident {
+    let mut snake = String::new();
+
+    for (i, char) in ident.to_string().chars().enumerate() {
+        if char.is_uppercase() {
+            if i != 0 {
+                snake.push('_');
+            }
+            snake.extend(char.to_lowercase());
+        } else {
+            snake.push(char);
+        }
+    }
+
+    // Keywords aren't valid parameter names.
+    if syn::parse_str::<Ident>(&snake).is_err() {
+        snake.push('_');
+    }
+
+    Ident::new(&snake, ident.span())
+}
+
 fn derive_enum(vis: Visibility, name: Ident, data: DataEnum) -> proc_macro2::TokenStream {
     let variant_count = data.variants.len() as u32;
 
+    // Expression of the value of each variant given to its arm of
+    // `match_variants`, variants without fields have an arm without parameter.
+    let variant_expressions = data.variants.iter().map(|variant| {
+        let expression_name = format_ident!("{}Expression", variant.ident);
+
+        match &variant.fields {
+            Fields::Named(fields) if !fields.named.is_empty() => {
+                let field_names = fields.named.iter().map(|field| &field.ident);
+                let field_types = fields.named.iter().map(|field| &field.ty);
+
+                quote! {
+                    #vis struct #expression_name {
+                        #(pub #field_names: <#field_types as ::database::Schema>::Expression,)*
+                    }
+                }
+            }
+            Fields::Unnamed(fields) if !fields.unnamed.is_empty() => {
+                let field_types = fields.unnamed.iter().map(|field| &field.ty);
+
+                quote! {
+                    #vis struct #expression_name(
+                        #(pub <#field_types as ::database::Schema>::Expression,)*
+                    );
+                }
+            }
+            Fields::Named(_) | Fields::Unnamed(_) | Fields::Unit => quote! {},
+        }
+    });
+
+    let arm_names = data
+        .variants
+        .iter()
+        .map(|variant| snake_case(&variant.ident))
+        .collect::<Vec<_>>();
+    let arm_types = (0..data.variants.len())
+        .map(|i| format_ident!("__Arm{i}"))
+        .collect::<Vec<_>>();
+
+    let arm_closures = data
+        .variants
+        .iter()
+        .zip(&arm_types)
+        .map(|(variant, arm_type)| {
+            let expression_name = format_ident!("{}Expression", variant.ident);
+
+            match &variant.fields {
+                Fields::Named(fields) if !fields.named.is_empty() => {
+                    quote! { impl FnOnce(#expression_name) -> #arm_type }
+                }
+                Fields::Unnamed(fields) if !fields.unnamed.is_empty() => {
+                    quote! { impl FnOnce(#expression_name) -> #arm_type }
+                }
+                Fields::Named(_) | Fields::Unnamed(_) | Fields::Unit => {
+                    quote! { impl FnOnce() -> #arm_type }
+                }
+            }
+        });
+
+    let arms = data.variants.iter().zip(&arm_names).map(|(variant, arm_name)| {
+        let expression_name = format_ident!("{}Expression", variant.ident);
+
+        let field_path = |index: usize| {
+            let index = index as u32;
+            quote! { path.iter().copied().chain([#index]).collect() }
+        };
+
+        match &variant.fields {
+            Fields::Named(fields) if !fields.named.is_empty() => {
+                let field_names = fields.named.iter().map(|field| &field.ident);
+                let field_types = fields.named.iter().map(|field| &field.ty);
+                let field_paths = (0..fields.named.len()).map(field_path);
+
+                quote! {
+                    ::database::__internal::variant_arm(|path| #arm_name(#expression_name {
+                        #(#field_names: <<#field_types as ::database::Schema>::Expression as ::database::FromPath>::from_path(#field_paths),)*
+                    }))
+                }
+            }
+            Fields::Unnamed(fields) if !fields.unnamed.is_empty() => {
+                let field_types = fields.unnamed.iter().map(|field| &field.ty);
+                let field_paths = (0..fields.unnamed.len()).map(field_path);
+
+                quote! {
+                    ::database::__internal::variant_arm(|path| #arm_name(#expression_name(
+                        #(<<#field_types as ::database::Schema>::Expression as ::database::FromPath>::from_path(#field_paths),)*
+                    )))
+                }
+            }
+            Fields::Named(_) | Fields::Unnamed(_) | Fields::Unit => quote! {
+                ::database::__internal::variant_arm(|_| #arm_name())
+            },
+        }
+    });
+
+    // Arms are nested as `(arm_0, (arm_1, ()))`, see `database::MatchArms`.
+    let arms_type = arm_types.iter().rev().fold(
+        quote! { () },
+        |rest, arm_type| quote! { (#arm_type, #rest) },
+    );
+    let arms = arms
+        .collect::<Vec<_>>()
+        .into_iter()
+        .rev()
+        .fold(quote! { () }, |rest, arm| quote! { (#arm, #rest) });
+
+    // Constructor of the value of each variant from expressions of its fields,
+    // named after the variant like its arm.
+    let constructors = data
+        .variants
+        .iter()
+        .zip(&arm_names)
+        .enumerate()
+        .map(|(i, (variant, constructor_name))| {
+            let discriminant = i as u32;
+
+            let field_names = match &variant.fields {
+                Fields::Named(fields) => fields
+                    .named
+                    .iter()
+                    .map(|field| field.ident.clone().unwrap())
+                    .collect(),
+                Fields::Unnamed(fields) => (0..fields.unnamed.len())
+                    .map(|index| format_ident!("field_{index}"))
+                    .collect(),
+                Fields::Unit => Vec::new(),
+            };
+            let field_types = variant.fields.iter().map(|field| &field.ty);
+            let field_generics = (0..field_names.len())
+                .map(|index| format_ident!("__Field{index}"))
+                .collect::<Vec<_>>();
+
+            // Fields are nested as `(field_0, (field_1, ()))`, see
+            // `database::VariantFields`.
+            let fields_type = field_generics.iter().rev().fold(
+                quote! { () },
+                |rest, field_generic| quote! { (#field_generic, #rest) },
+            );
+            let fields = field_names.iter().rev().fold(
+                quote! { () },
+                |rest, field_name| quote! { (#field_name, #rest) },
+            );
+
+            quote! {
+                pub fn #constructor_name<#(#field_generics: ::database::Expression<Target = #field_types>,)*>(
+                    #(#field_names: #field_generics,)*
+                ) -> ::database::VariantExpression<#fields_type, #name> {
+                    ::database::__internal::variant(#discriminant, #fields)
+                }
+            }
+        });
+
+    let variant_checks = arm_names.iter().enumerate().map(|(i, arm_name)| {
+        let discriminant = i as u32;
+        let check_name = format_ident!("is_{}", arm_name.to_string().trim_end_matches('_'));
+
+        quote! {
+            pub fn #check_name(self) -> ::database::IsVariantExpression<Self> {
+                ::database::__internal::is_variant(self, #discriminant)
+            }
+        }
+    });
+
     let write_schemas = data.variants.iter().map(|variant| match &variant.fields {
         Fields::Named(fields) if fields.named.len() != 0 => {
             let field_count = fields.named.len() as u32;
@@ -514,6 +780,30 @@ fn derive_enum(vis: Visibility, name: Ident, data: DataEnum) -> proc_macro2::Tok
                 }
             }
 
+            #(#variant_expressions)*
+
+            #[allow(dead_code)]
+            impl Expression {
+                /// Value of the arm of the variant of this value, each arm
+                /// being given the fields of its variant.
+                pub fn match_variants<__Out: ::database::Schema, #(#arm_types: ::database::Expression<Target = __Out>,)*>(
+                    self,
+                    #(#arm_names: #arm_closures,)*
+                ) -> ::database::MatchExpression<Self, #arms_type, __Out> {
+                    ::database::__internal::match_variants(self, #arms)
+                }
+
+                #(
+                    /// Value of this variant with the given fields.
+                    #constructors
+                )*
+
+                #(
+                    /// Whether the value is of this variant.
+                    #variant_checks
+                )*
+            }
+
             impl ::database::Schema for #name {
                 type Expression = Expression;
 
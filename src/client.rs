@@ -5,7 +5,10 @@ use tokio::{
     net::{TcpStream, ToSocketAddrs},
 };
 
-use crate::{request_discriminant, Expression, FromPath, Schema, SchemaNode, Scope};
+use crate::{
+    io_error, request_discriminant, response_discriminant, Error, Expression, FromPath, QueryError,
+    Schema, SchemaNode, Scope,
+};
 
 pub struct Client<S: Schema + Send + Sync, St: AsyncReadExt + AsyncWriteExt + Unpin + Send> {
     stream: St,
@@ -53,7 +56,7 @@ impl<S: Schema + Send + Sync, St: AsyncReadExt + AsyncWriteExt + Unpin + Send> C
     pub async fn query<E: Expression>(
         &mut self,
         query: impl FnOnce(S::Expression) -> E,
-    ) -> io::Result<E::Target> {
+    ) -> Result<E::Target, Error> {
         Scope::create();
         let expression = (query)(<S::Expression as FromPath>::from_path(vec![0]));
         Scope::delete();
@@ -62,6 +65,15 @@ impl<S: Schema + Send + Sync, St: AsyncReadExt + AsyncWriteExt + Unpin + Send> C
 
         expression.write(&mut self.stream).await?;
 
-        E::Target::read_value(&mut self.stream).await
+        match self.stream.read_u8().await? {
+            response_discriminant::VALUE => Ok(E::Target::read_value(&mut self.stream).await?),
+            response_discriminant::ERROR => {
+                Err(Error::Query(QueryError::read(&mut self.stream).await?))
+            }
+            _ => Err(Error::Io(io_error!(
+                InvalidData,
+                "invalid discriminant for response"
+            ))),
+        }
     }
 }
@@ -0,0 +1,232 @@
+use std::{
+    error,
+    fmt::{self, Display},
+    io,
+};
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+use crate::io_error;
+
+/// Error returned by [`Client::query`](crate::Client::query).
+#[derive(Debug)]
+pub enum Error {
+    /// The communication with the server failed.
+    Io(io::Error),
+    /// The server couldn't evaluate the query.
+    Query(QueryError),
+}
+
+/// Reason why the server couldn't evaluate a query.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QueryError {
+    /// A path expression doesn't lead to any value.
+    InvalidPath(Vec<u32>),
+    /// An operand of the expression with the given discriminant (see
+    /// [`expression_discriminant`](crate::expression_discriminant)) isn't of
+    /// the kind of value this expression operates on.
+    ///
+    /// Queries are type checked against the schema of the database before
+    /// being evaluated, see [`ExpressionNode::type_check`](crate::ExpressionNode::type_check).
+    InvalidOperand(u8),
+    /// An index is greater than the length of the list it indexes.
+    IndexOutOfBounds { index: u32, length: u32 },
+    /// The result of the arithmetic expression with the given discriminant
+    /// doesn't fit into its integer type.
+    Overflow(u8),
+    /// An integer division or remainder has a zero divisor.
+    DivisionByZero,
+    /// A [`Pattern`](crate::Pattern) can't be compiled, for the given reason.
+    InvalidPattern(String),
+}
+
+pub mod query_error_discriminant {
+    pub const INVALID_PATH: u8 = 0;
+    pub const INVALID_OPERAND: u8 = 1;
+    pub const INDEX_OUT_OF_BOUNDS: u8 = 2;
+    pub const OVERFLOW: u8 = 3;
+    pub const DIVISION_BY_ZERO: u8 = 4;
+    pub const INVALID_PATTERN: u8 = 5;
+}
+
+impl QueryError {
+    fn discriminant(&self) -> u8 {
+        match self {
+            Self::InvalidPath(_) => query_error_discriminant::INVALID_PATH,
+            Self::InvalidOperand(_) => query_error_discriminant::INVALID_OPERAND,
+            Self::IndexOutOfBounds { .. } => query_error_discriminant::INDEX_OUT_OF_BOUNDS,
+            Self::Overflow(_) => query_error_discriminant::OVERFLOW,
+            Self::DivisionByZero => query_error_discriminant::DIVISION_BY_ZERO,
+            Self::InvalidPattern(_) => query_error_discriminant::INVALID_PATTERN,
+        }
+    }
+
+    pub async fn read(read: &mut (impl AsyncReadExt + Unpin)) -> io::Result<Self> {
+        let discriminant = read.read_u8().await?;
+
+        let error = match discriminant {
+            query_error_discriminant::INVALID_PATH => {
+                let length: usize = read.read_u32().await?.try_into().map_err(|_| {
+                    io_error!(
+                        OutOfMemory,
+                        "path length doesn't fit into a pointer sized unsigned integer",
+                    )
+                })?;
+
+                let mut path = Vec::new();
+                path.try_reserve(length)
+                    .map_err(|_| io_error!(OutOfMemory, "allocation of memory for path failed"))?;
+
+                for _ in 0..length {
+                    path.push(read.read_u32().await?);
+                }
+
+                Self::InvalidPath(path)
+            }
+            query_error_discriminant::INVALID_OPERAND => {
+                Self::InvalidOperand(read.read_u8().await?)
+            }
+            query_error_discriminant::INDEX_OUT_OF_BOUNDS => Self::IndexOutOfBounds {
+                index: read.read_u32().await?,
+                length: read.read_u32().await?,
+            },
+            query_error_discriminant::OVERFLOW => Self::Overflow(read.read_u8().await?),
+            query_error_discriminant::DIVISION_BY_ZERO => Self::DivisionByZero,
+            query_error_discriminant::INVALID_PATTERN => {
+                let length: usize = read.read_u32().await?.try_into().map_err(|_| {
+                    io_error!(
+                        OutOfMemory,
+                        "reason length doesn't fit into a pointer sized unsigned integer",
+                    )
+                })?;
+
+                let mut reason = Vec::new();
+                reason.try_reserve(length).map_err(|_| {
+                    io_error!(OutOfMemory, "allocation of memory for reason failed")
+                })?;
+                reason.resize(length, 0);
+
+                read.read_exact(&mut reason).await?;
+
+                Self::InvalidPattern(
+                    String::from_utf8(reason)
+                        .map_err(|_| io_error!(InvalidData, "reason isn't valid utf-8"))?,
+                )
+            }
+            _ => {
+                return Err(io_error!(
+                    InvalidData,
+                    "invalid discriminant while parsing query error",
+                ));
+            }
+        };
+
+        debug_assert_eq!(error.discriminant(), discriminant);
+
+        Ok(error)
+    }
+
+    pub async fn write(&self, write: &mut (impl AsyncWriteExt + Unpin)) -> io::Result<()> {
+        write.write_u8(self.discriminant()).await?;
+
+        match self {
+            Self::InvalidPath(path) => {
+                write
+                    .write_u32(path.len().try_into().map_err(|_| {
+                        io_error!(
+                            OutOfMemory,
+                            "path length doesn't fit into a 32 bit unsigned integer",
+                        )
+                    })?)
+                    .await?;
+
+                for segment in path {
+                    write.write_u32(*segment).await?;
+                }
+            }
+            Self::InvalidOperand(expression) | Self::Overflow(expression) => {
+                write.write_u8(*expression).await?
+            }
+            Self::IndexOutOfBounds { index, length } => {
+                write.write_u32(*index).await?;
+                write.write_u32(*length).await?;
+            }
+            Self::DivisionByZero => {}
+            Self::InvalidPattern(reason) => {
+                write
+                    .write_u32(reason.len().try_into().map_err(|_| {
+                        io_error!(
+                            OutOfMemory,
+                            "reason length doesn't fit into a 32 bit unsigned integer",
+                        )
+                    })?)
+                    .await?;
+                write.write_all(reason.as_bytes()).await?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => Display::fmt(err, f),
+            Self::Query(err) => Display::fmt(err, f),
+        }
+    }
+}
+
+impl Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidPath(path) => write!(f, "path {path:?} doesn't lead to any value"),
+            Self::InvalidOperand(expression) => write!(
+                f,
+                "invalid operand for expression with discriminant {expression}"
+            ),
+            Self::IndexOutOfBounds { index, length } => write!(
+                f,
+                "index {index} is out of bounds for a list of length {length}"
+            ),
+            Self::Overflow(expression) => {
+                write!(f, "overflow in expression with discriminant {expression}")
+            }
+            Self::DivisionByZero => write!(f, "division by zero"),
+            Self::InvalidPattern(reason) => write!(f, "invalid pattern: {reason}"),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            Self::Query(err) => Some(err),
+        }
+    }
+}
+
+impl error::Error for QueryError {}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<QueryError> for Error {
+    fn from(err: QueryError) -> Self {
+        Self::Query(err)
+    }
+}
+
+impl From<Error> for io::Error {
+    fn from(err: Error) -> Self {
+        match err {
+            Error::Io(err) => err,
+            Error::Query(err) => io::Error::new(io::ErrorKind::InvalidInput, err),
+        }
+    }
+}
@@ -2,10 +2,158 @@ use std::{future::Future, io, marker::PhantomData};
 
 use tokio::io::AsyncWriteExt;
 
-use crate::{expression_discriminant, Expression, Schema};
+use crate::{
+    expression_discriminant, Expression, MatchArms, Numeric, Pattern, Schema, SortKey,
+    VariantFields,
+};
 
 pub struct SetExpression<L: Expression, R: Expression>(pub(crate) L, pub(crate) R);
 pub struct EqualExpression<L: Expression, R: Expression>(pub(crate) L, pub(crate) R);
+pub struct NotEqualExpression<L: Expression, R: Expression>(pub(crate) L, pub(crate) R);
+pub struct PushExpression<L: Expression, R: Expression>(pub(crate) L, pub(crate) R);
+pub struct PopExpression<L: Expression, Out: Schema + Send + Sync>(
+    pub(crate) L,
+    pub(crate) PhantomData<Out>,
+);
+pub struct RemoveExpression<L: Expression, I: Expression, Out: Schema + Send + Sync>(
+    pub(crate) L,
+    pub(crate) I,
+    pub(crate) PhantomData<Out>,
+);
+pub struct ClearExpression<L: Expression>(pub(crate) L);
+pub struct TruncateExpression<L: Expression, I: Expression>(pub(crate) L, pub(crate) I);
+pub struct ExtendExpression<L: Expression, R: Expression>(pub(crate) L, pub(crate) R);
+pub struct SlotMapInsertExpression<L: Expression, R: Expression, K: Schema>(
+    pub(crate) L,
+    pub(crate) R,
+    pub(crate) PhantomData<K>,
+);
+pub struct SlotMapRemoveExpression<L: Expression, K: Expression, Out: Schema + Send + Sync>(
+    pub(crate) L,
+    pub(crate) K,
+    pub(crate) PhantomData<Out>,
+);
+pub struct MapGetExpression<L: Expression, K: Expression, Out: Schema + Send + Sync>(
+    pub(crate) L,
+    pub(crate) K,
+    pub(crate) PhantomData<Out>,
+);
+pub struct MapInsertExpression<
+    L: Expression,
+    K: Expression,
+    V: Expression,
+    Out: Schema + Send + Sync,
+>(
+    pub(crate) L,
+    pub(crate) K,
+    pub(crate) V,
+    pub(crate) PhantomData<Out>,
+);
+pub struct MapRemoveExpression<L: Expression, K: Expression, Out: Schema + Send + Sync>(
+    pub(crate) L,
+    pub(crate) K,
+    pub(crate) PhantomData<Out>,
+);
+pub struct MapContainsKeyExpression<L: Expression, K: Expression>(pub(crate) L, pub(crate) K);
+pub struct MapKeysExpression<L: Expression, Out: Schema + Send + Sync>(
+    pub(crate) L,
+    pub(crate) PhantomData<Out>,
+);
+pub struct MapValuesExpression<L: Expression, Out: Schema + Send + Sync>(
+    pub(crate) L,
+    pub(crate) PhantomData<Out>,
+);
+pub struct MapEntriesExpression<L: Expression, Out: Schema + Send + Sync>(
+    pub(crate) L,
+    pub(crate) PhantomData<Out>,
+);
+pub struct SetContainsExpression<L: Expression, R: Expression>(pub(crate) L, pub(crate) R);
+pub struct SetInsertExpression<L: Expression, R: Expression>(pub(crate) L, pub(crate) R);
+pub struct SetRemoveExpression<L: Expression, R: Expression>(pub(crate) L, pub(crate) R);
+pub struct SetUnionExpression<L: Expression, R: Expression>(pub(crate) L, pub(crate) R);
+pub struct SetIntersectionExpression<L: Expression, R: Expression>(pub(crate) L, pub(crate) R);
+pub struct SetDifferenceExpression<L: Expression, R: Expression>(pub(crate) L, pub(crate) R);
+pub struct ConcatExpression<L: Expression, R: Expression>(pub(crate) L, pub(crate) R);
+pub struct StringContainsExpression<L: Expression, R: Expression>(pub(crate) L, pub(crate) R);
+pub struct StartsWithExpression<L: Expression, R: Expression>(pub(crate) L, pub(crate) R);
+pub struct EndsWithExpression<L: Expression, R: Expression>(pub(crate) L, pub(crate) R);
+pub struct StringLengthExpression<L: Expression>(pub(crate) L);
+pub struct SubstringExpression<L: Expression, S: Expression, N: Expression>(
+    pub(crate) L,
+    pub(crate) S,
+    pub(crate) N,
+);
+pub struct ToLowercaseExpression<L: Expression>(pub(crate) L);
+pub struct ToUppercaseExpression<L: Expression>(pub(crate) L);
+pub struct MatchesExpression<L: Expression>(pub(crate) L, pub(crate) Pattern);
+pub struct ListSumExpression<L: Expression, Out: Schema + Send + Sync>(
+    pub(crate) L,
+    pub(crate) PhantomData<Out>,
+);
+pub struct MinExpression<L: Expression, Out: Schema + Send + Sync>(
+    pub(crate) L,
+    pub(crate) PhantomData<Out>,
+);
+pub struct MaxExpression<L: Expression, Out: Schema + Send + Sync>(
+    pub(crate) L,
+    pub(crate) PhantomData<Out>,
+);
+pub struct AverageExpression<L: Expression>(pub(crate) L);
+pub struct MinByExpression<L: Expression, R: Expression, Out: Schema + Send + Sync>(
+    pub(crate) L,
+    pub(crate) R,
+    pub(crate) PhantomData<Out>,
+);
+pub struct MaxByExpression<L: Expression, R: Expression, Out: Schema + Send + Sync>(
+    pub(crate) L,
+    pub(crate) R,
+    pub(crate) PhantomData<Out>,
+);
+pub struct CountExpression<L: Expression, R: Expression>(pub(crate) L, pub(crate) R);
+pub struct SortedExpression<L: Expression, K: SortKey>(pub(crate) L, pub(crate) K);
+pub struct SortExpression<L: Expression, K: SortKey>(pub(crate) L, pub(crate) K);
+pub struct ReversedExpression<L: Expression>(pub(crate) L);
+pub struct ReverseExpression<L: Expression>(pub(crate) L);
+pub struct GroupByExpression<L: Expression, R: Expression, Out: Schema>(
+    pub(crate) L,
+    pub(crate) R,
+    pub(crate) PhantomData<Out>,
+);
+pub struct TransformValuesExpression<L: Expression, R: Expression, Out: Schema>(
+    pub(crate) L,
+    pub(crate) R,
+    pub(crate) PhantomData<Out>,
+);
+pub struct JoinExpression<L: Expression, K: Expression, M: Expression, Out: Schema + Send + Sync>(
+    pub(crate) L,
+    pub(crate) K,
+    pub(crate) M,
+    pub(crate) PhantomData<Out>,
+);
+pub struct MatchExpression<L: Expression, A: MatchArms<Out>, Out: Schema>(
+    pub(crate) L,
+    pub(crate) A,
+    pub(crate) PhantomData<Out>,
+);
+pub struct VariantExpression<F: VariantFields, Out: Schema>(
+    pub(crate) u32,
+    pub(crate) F,
+    pub(crate) PhantomData<Out>,
+);
+pub struct IsVariantExpression<L: Expression>(pub(crate) L, pub(crate) u32);
+pub struct SkipExpression<L: Expression, I: Expression>(pub(crate) L, pub(crate) I);
+pub struct TakeExpression<L: Expression, I: Expression>(pub(crate) L, pub(crate) I);
+pub struct SlotMapPageExpression<
+    L: Expression,
+    C: Expression,
+    I: Expression,
+    Out: Schema + Send + Sync,
+>(
+    pub(crate) L,
+    pub(crate) C,
+    pub(crate) I,
+    pub(crate) PhantomData<Out>,
+);
 pub struct FilterExpression<L: Expression, R: Expression>(pub(crate) L, pub(crate) R);
 pub struct MapExpression<L: Expression, R: Expression, Out: Schema>(
     pub(crate) L,
@@ -19,6 +167,9 @@ pub struct InsertExpression<L: Expression, I: Expression, R: Expression>(
     pub(crate) R,
 );
 pub struct AndExpression<L: Expression, R: Expression>(pub(crate) L, pub(crate) R);
+pub struct OrExpression<L: Expression, R: Expression>(pub(crate) L, pub(crate) R);
+pub struct NotExpression<E: Expression>(pub(crate) E);
+pub struct XorExpression<L: Expression, R: Expression>(pub(crate) L, pub(crate) R);
 pub struct MapVariantExpression<L: Expression, R: Expression, Out: Schema + Send + Sync>(
     pub(crate) L,
     pub(crate) u32,
@@ -27,6 +178,7 @@ pub struct MapVariantExpression<L: Expression, R: Expression, Out: Schema + Send
 );
 pub struct FuseExpression<E: Expression, Out: Schema>(pub(crate) E, pub(crate) PhantomData<Out>);
 pub struct ChainExpression<L: Expression, R: Expression>(pub(crate) L, pub(crate) R);
+pub struct LetExpression<L: Expression, R: Expression>(pub(crate) L, pub(crate) R);
 pub struct GetExpression<L: Expression, R: Expression, Out: Schema + Send + Sync>(
     pub(crate) L,
     pub(crate) R,
@@ -43,6 +195,15 @@ where
     T: Schema,
     I: Expression<Target = T>,
     E: Expression<Target = T>;
+pub struct AddExpression<L: Expression, R: Expression>(pub(crate) L, pub(crate) R);
+pub struct SubExpression<L: Expression, R: Expression>(pub(crate) L, pub(crate) R);
+pub struct MulExpression<L: Expression, R: Expression>(pub(crate) L, pub(crate) R);
+pub struct DivExpression<L: Expression, R: Expression>(pub(crate) L, pub(crate) R);
+pub struct RemExpression<L: Expression, R: Expression>(pub(crate) L, pub(crate) R);
+pub struct LessExpression<L: Expression, R: Expression>(pub(crate) L, pub(crate) R);
+pub struct LessEqualExpression<L: Expression, R: Expression>(pub(crate) L, pub(crate) R);
+pub struct GreaterExpression<L: Expression, R: Expression>(pub(crate) L, pub(crate) R);
+pub struct GreaterEqualExpression<L: Expression, R: Expression>(pub(crate) L, pub(crate) R);
 
 impl<L: Expression, R: Expression> Expression for SetExpression<L, R> {
     type Target = L::Target;
@@ -76,6 +237,22 @@ impl<L: Expression, R: Expression> Expression for EqualExpression<L, R> {
     }
 }
 
+impl<L: Expression, R: Expression> Expression for NotEqualExpression<L, R> {
+    type Target = bool;
+
+    fn write(
+        self,
+        write: &mut (impl AsyncWriteExt + Unpin + Send),
+    ) -> impl Future<Output = io::Result<()>> {
+        async {
+            write.write_u8(expression_discriminant::NOT_EQUAL).await?;
+            Box::pin(self.0.write(write)).await?;
+            Box::pin(self.1.write(write)).await?;
+            Ok(())
+        }
+    }
+}
+
 impl<L: Expression, R: Expression> Expression for FilterExpression<L, R>
 where
     L::Target: Send + Sync,
@@ -165,6 +342,62 @@ where
     }
 }
 
+impl<L: Expression, R: Expression> Expression for OrExpression<L, R>
+where
+    L::Target: Send + Sync,
+{
+    type Target = L::Target;
+
+    fn write(
+        self,
+        write: &mut (impl AsyncWriteExt + Unpin + Send),
+    ) -> impl Future<Output = io::Result<()>> {
+        async {
+            write.write_u8(expression_discriminant::OR).await?;
+            Box::pin(self.0.write(write)).await?;
+            Box::pin(self.1.write(write)).await?;
+            Ok(())
+        }
+    }
+}
+
+impl<L: Expression, R: Expression> Expression for XorExpression<L, R>
+where
+    L::Target: Send + Sync,
+{
+    type Target = L::Target;
+
+    fn write(
+        self,
+        write: &mut (impl AsyncWriteExt + Unpin + Send),
+    ) -> impl Future<Output = io::Result<()>> {
+        async {
+            write.write_u8(expression_discriminant::XOR).await?;
+            Box::pin(self.0.write(write)).await?;
+            Box::pin(self.1.write(write)).await?;
+            Ok(())
+        }
+    }
+}
+
+impl<E: Expression> Expression for NotExpression<E>
+where
+    E::Target: Send + Sync,
+{
+    type Target = E::Target;
+
+    fn write(
+        self,
+        write: &mut (impl AsyncWriteExt + Unpin + Send),
+    ) -> impl Future<Output = io::Result<()>> {
+        async {
+            write.write_u8(expression_discriminant::NOT).await?;
+            Box::pin(self.0.write(write)).await?;
+            Ok(())
+        }
+    }
+}
+
 impl<L: Expression, R: Expression, Out: Schema + Send + Sync> Expression
     for MapVariantExpression<L, R, Out>
 {
@@ -256,3 +489,707 @@ where
         }
     }
 }
+
+macro_rules! impl_arithmetic_expression {
+    ($($expression:ident $discriminant:ident;)*) => {
+        $(
+            impl<L: Expression, R: Expression> Expression for $expression<L, R>
+            where
+                L::Target: Numeric,
+            {
+                type Target = <L::Target as Numeric>::Output;
+
+                fn write(
+                    self,
+                    write: &mut (impl AsyncWriteExt + Unpin + Send),
+                ) -> impl Future<Output = io::Result<()>> {
+                    async {
+                        write.write_u8(expression_discriminant::$discriminant).await?;
+                        Box::pin(self.0.write(write)).await?;
+                        Box::pin(self.1.write(write)).await?;
+                        Ok(())
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_arithmetic_expression!(
+    AddExpression ADD;
+    SubExpression SUB;
+    MulExpression MUL;
+    DivExpression DIV;
+    RemExpression REM;
+);
+
+macro_rules! impl_comparison_expression {
+    ($($expression:ident $discriminant:ident;)*) => {
+        $(
+            impl<L: Expression, R: Expression> Expression for $expression<L, R> {
+                type Target = bool;
+
+                fn write(
+                    self,
+                    write: &mut (impl AsyncWriteExt + Unpin + Send),
+                ) -> impl Future<Output = io::Result<()>> {
+                    async {
+                        write.write_u8(expression_discriminant::$discriminant).await?;
+                        Box::pin(self.0.write(write)).await?;
+                        Box::pin(self.1.write(write)).await?;
+                        Ok(())
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_comparison_expression!(
+    LessExpression LESS;
+    LessEqualExpression LESS_EQUAL;
+    GreaterExpression GREATER;
+    GreaterEqualExpression GREATER_EQUAL;
+);
+
+macro_rules! impl_binary_expression {
+    ($($expression:ident $discriminant:ident => $target:ty;)*) => {
+        $(
+            impl<L: Expression, R: Expression> Expression for $expression<L, R> {
+                type Target = $target;
+
+                fn write(
+                    self,
+                    write: &mut (impl AsyncWriteExt + Unpin + Send),
+                ) -> impl Future<Output = io::Result<()>> {
+                    async {
+                        write.write_u8(expression_discriminant::$discriminant).await?;
+                        Box::pin(self.0.write(write)).await?;
+                        Box::pin(self.1.write(write)).await?;
+                        Ok(())
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_binary_expression!(
+    SetContainsExpression SET_CONTAINS => bool;
+    SetInsertExpression SET_INSERT => bool;
+    SetRemoveExpression SET_REMOVE => bool;
+    SetUnionExpression SET_UNION => L::Target;
+    SetIntersectionExpression SET_INTERSECTION => L::Target;
+    SetDifferenceExpression SET_DIFFERENCE => L::Target;
+    ConcatExpression CONCAT => String;
+    StringContainsExpression STRING_CONTAINS => bool;
+    StartsWithExpression STARTS_WITH => bool;
+    EndsWithExpression ENDS_WITH => bool;
+    CountExpression COUNT => u32;
+    SkipExpression SKIP => L::Target;
+    TakeExpression TAKE => L::Target;
+    LetExpression LET => R::Target;
+);
+
+macro_rules! impl_sort_expression {
+    ($($expression:ident $discriminant:ident => $target:ty;)*) => {
+        $(
+            impl<L: Expression, K: SortKey> Expression for $expression<L, K> {
+                type Target = $target;
+
+                fn write(
+                    self,
+                    write: &mut (impl AsyncWriteExt + Unpin + Send),
+                ) -> impl Future<Output = io::Result<()>> {
+                    async {
+                        write.write_u8(expression_discriminant::$discriminant).await?;
+                        Box::pin(self.0.write(write)).await?;
+                        write.write_u32(K::LENGTH).await?;
+                        Box::pin(self.1.write_keys(write)).await?;
+                        Ok(())
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_sort_expression!(
+    SortedExpression SORTED => L::Target;
+    SortExpression SORT => ();
+);
+
+macro_rules! impl_reverse_expression {
+    ($($expression:ident $discriminant:ident => $target:ty;)*) => {
+        $(
+            impl<L: Expression> Expression for $expression<L> {
+                type Target = $target;
+
+                fn write(
+                    self,
+                    write: &mut (impl AsyncWriteExt + Unpin + Send),
+                ) -> impl Future<Output = io::Result<()>> {
+                    async {
+                        write.write_u8(expression_discriminant::$discriminant).await?;
+                        Box::pin(self.0.write(write)).await?;
+                        Ok(())
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_reverse_expression!(
+    ReversedExpression REVERSED => L::Target;
+    ReverseExpression REVERSE => ();
+);
+
+impl<L: Expression> Expression for StringLengthExpression<L> {
+    type Target = u32;
+
+    fn write(
+        self,
+        write: &mut (impl AsyncWriteExt + Unpin + Send),
+    ) -> impl Future<Output = io::Result<()>> {
+        async {
+            write
+                .write_u8(expression_discriminant::STRING_LENGTH)
+                .await?;
+            Box::pin(self.0.write(write)).await?;
+            Ok(())
+        }
+    }
+}
+
+impl<L: Expression, S: Expression, N: Expression> Expression for SubstringExpression<L, S, N> {
+    type Target = String;
+
+    fn write(
+        self,
+        write: &mut (impl AsyncWriteExt + Unpin + Send),
+    ) -> impl Future<Output = io::Result<()>> {
+        async {
+            write.write_u8(expression_discriminant::SUBSTRING).await?;
+            Box::pin(self.0.write(write)).await?;
+            Box::pin(self.1.write(write)).await?;
+            Box::pin(self.2.write(write)).await?;
+            Ok(())
+        }
+    }
+}
+
+impl<L: Expression> Expression for ToLowercaseExpression<L> {
+    type Target = String;
+
+    fn write(
+        self,
+        write: &mut (impl AsyncWriteExt + Unpin + Send),
+    ) -> impl Future<Output = io::Result<()>> {
+        async {
+            write
+                .write_u8(expression_discriminant::TO_LOWERCASE)
+                .await?;
+            Box::pin(self.0.write(write)).await?;
+            Ok(())
+        }
+    }
+}
+
+impl<L: Expression, Out: Schema + Send + Sync> Expression for ListSumExpression<L, Out> {
+    type Target = Out;
+
+    fn write(
+        self,
+        write: &mut (impl AsyncWriteExt + Unpin + Send),
+    ) -> impl Future<Output = io::Result<()>> {
+        async {
+            write.write_u8(expression_discriminant::LIST_SUM).await?;
+            Box::pin(self.0.write(write)).await?;
+            Out::write_schema(write).await?;
+            Ok(())
+        }
+    }
+}
+
+impl<L: Expression, Out: Schema + Send + Sync> Expression for MinExpression<L, Out> {
+    type Target = Option<Out>;
+
+    fn write(
+        self,
+        write: &mut (impl AsyncWriteExt + Unpin + Send),
+    ) -> impl Future<Output = io::Result<()>> {
+        async {
+            write.write_u8(expression_discriminant::MIN).await?;
+            Box::pin(self.0.write(write)).await?;
+            Ok(())
+        }
+    }
+}
+
+impl<L: Expression, Out: Schema + Send + Sync> Expression for MaxExpression<L, Out> {
+    type Target = Option<Out>;
+
+    fn write(
+        self,
+        write: &mut (impl AsyncWriteExt + Unpin + Send),
+    ) -> impl Future<Output = io::Result<()>> {
+        async {
+            write.write_u8(expression_discriminant::MAX).await?;
+            Box::pin(self.0.write(write)).await?;
+            Ok(())
+        }
+    }
+}
+
+impl<L: Expression> Expression for AverageExpression<L> {
+    type Target = Option<f64>;
+
+    fn write(
+        self,
+        write: &mut (impl AsyncWriteExt + Unpin + Send),
+    ) -> impl Future<Output = io::Result<()>> {
+        async {
+            write.write_u8(expression_discriminant::AVERAGE).await?;
+            Box::pin(self.0.write(write)).await?;
+            Ok(())
+        }
+    }
+}
+
+impl<L: Expression, R: Expression, Out: Schema + Send + Sync> Expression
+    for MinByExpression<L, R, Out>
+{
+    type Target = Option<Out>;
+
+    fn write(
+        self,
+        write: &mut (impl AsyncWriteExt + Unpin + Send),
+    ) -> impl Future<Output = io::Result<()>> {
+        async {
+            write.write_u8(expression_discriminant::MIN_BY).await?;
+            Box::pin(self.0.write(write)).await?;
+            Box::pin(self.1.write(write)).await?;
+            Ok(())
+        }
+    }
+}
+
+impl<L: Expression, R: Expression, Out: Schema + Send + Sync> Expression
+    for MaxByExpression<L, R, Out>
+{
+    type Target = Option<Out>;
+
+    fn write(
+        self,
+        write: &mut (impl AsyncWriteExt + Unpin + Send),
+    ) -> impl Future<Output = io::Result<()>> {
+        async {
+            write.write_u8(expression_discriminant::MAX_BY).await?;
+            Box::pin(self.0.write(write)).await?;
+            Box::pin(self.1.write(write)).await?;
+            Ok(())
+        }
+    }
+}
+
+impl<L: Expression> Expression for MatchesExpression<L> {
+    type Target = bool;
+
+    fn write(
+        self,
+        write: &mut (impl AsyncWriteExt + Unpin + Send),
+    ) -> impl Future<Output = io::Result<()>> {
+        async move {
+            write.write_u8(expression_discriminant::MATCHES).await?;
+            Box::pin(self.0.write(write)).await?;
+            self.1.write(write).await?;
+            Ok(())
+        }
+    }
+}
+
+impl<L: Expression> Expression for ToUppercaseExpression<L> {
+    type Target = String;
+
+    fn write(
+        self,
+        write: &mut (impl AsyncWriteExt + Unpin + Send),
+    ) -> impl Future<Output = io::Result<()>> {
+        async {
+            write
+                .write_u8(expression_discriminant::TO_UPPERCASE)
+                .await?;
+            Box::pin(self.0.write(write)).await?;
+            Ok(())
+        }
+    }
+}
+
+impl<L: Expression, R: Expression> Expression for PushExpression<L, R> {
+    type Target = u32;
+
+    fn write(
+        self,
+        write: &mut (impl AsyncWriteExt + Unpin + Send),
+    ) -> impl Future<Output = io::Result<()>> {
+        async {
+            write.write_u8(expression_discriminant::PUSH).await?;
+            Box::pin(self.0.write(write)).await?;
+            Box::pin(self.1.write(write)).await?;
+            Ok(())
+        }
+    }
+}
+
+impl<L: Expression, Out: Schema + Send + Sync> Expression for PopExpression<L, Out> {
+    type Target = Option<Out>;
+
+    fn write(
+        self,
+        write: &mut (impl AsyncWriteExt + Unpin + Send),
+    ) -> impl Future<Output = io::Result<()>> {
+        async {
+            write.write_u8(expression_discriminant::POP).await?;
+            Box::pin(self.0.write(write)).await?;
+            Ok(())
+        }
+    }
+}
+
+impl<L: Expression, I: Expression, Out: Schema + Send + Sync> Expression
+    for RemoveExpression<L, I, Out>
+{
+    type Target = Out;
+
+    fn write(
+        self,
+        write: &mut (impl AsyncWriteExt + Unpin + Send),
+    ) -> impl Future<Output = io::Result<()>> {
+        async {
+            write.write_u8(expression_discriminant::REMOVE).await?;
+            Box::pin(self.0.write(write)).await?;
+            Box::pin(self.1.write(write)).await?;
+            Ok(())
+        }
+    }
+}
+
+impl<L: Expression> Expression for ClearExpression<L> {
+    type Target = L::Target;
+
+    fn write(
+        self,
+        write: &mut (impl AsyncWriteExt + Unpin + Send),
+    ) -> impl Future<Output = io::Result<()>> {
+        async {
+            write.write_u8(expression_discriminant::CLEAR).await?;
+            Box::pin(self.0.write(write)).await?;
+            Ok(())
+        }
+    }
+}
+
+impl<L: Expression, I: Expression> Expression for TruncateExpression<L, I> {
+    type Target = L::Target;
+
+    fn write(
+        self,
+        write: &mut (impl AsyncWriteExt + Unpin + Send),
+    ) -> impl Future<Output = io::Result<()>> {
+        async {
+            write.write_u8(expression_discriminant::TRUNCATE).await?;
+            Box::pin(self.0.write(write)).await?;
+            Box::pin(self.1.write(write)).await?;
+            Ok(())
+        }
+    }
+}
+
+impl<L: Expression, R: Expression> Expression for ExtendExpression<L, R> {
+    type Target = u32;
+
+    fn write(
+        self,
+        write: &mut (impl AsyncWriteExt + Unpin + Send),
+    ) -> impl Future<Output = io::Result<()>> {
+        async {
+            write.write_u8(expression_discriminant::EXTEND).await?;
+            Box::pin(self.0.write(write)).await?;
+            Box::pin(self.1.write(write)).await?;
+            Ok(())
+        }
+    }
+}
+
+impl<L: Expression, R: Expression, K: Schema> Expression for SlotMapInsertExpression<L, R, K> {
+    type Target = K;
+
+    fn write(
+        self,
+        write: &mut (impl AsyncWriteExt + Unpin + Send),
+    ) -> impl Future<Output = io::Result<()>> {
+        async {
+            write
+                .write_u8(expression_discriminant::SLOT_MAP_INSERT)
+                .await?;
+            Box::pin(self.0.write(write)).await?;
+            Box::pin(self.1.write(write)).await?;
+            Ok(())
+        }
+    }
+}
+
+macro_rules! impl_closure_expression {
+    ($($expression:ident $discriminant:ident;)*) => {
+        $(
+            impl<L: Expression, R: Expression, Out: Schema> Expression for $expression<L, R, Out> {
+                type Target = Out;
+
+                fn write(
+                    self,
+                    write: &mut (impl AsyncWriteExt + Unpin + Send),
+                ) -> impl Future<Output = io::Result<()>> {
+                    async {
+                        write.write_u8(expression_discriminant::$discriminant).await?;
+                        Box::pin(self.0.write(write)).await?;
+                        Box::pin(self.1.write(write)).await?;
+                        Ok(())
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_closure_expression!(
+    GroupByExpression GROUP_BY;
+    TransformValuesExpression TRANSFORM_VALUES;
+);
+
+impl<F: VariantFields, Out: Schema> Expression for VariantExpression<F, Out> {
+    type Target = Out;
+
+    fn write(
+        self,
+        write: &mut (impl AsyncWriteExt + Unpin + Send),
+    ) -> impl Future<Output = io::Result<()>> {
+        async move {
+            write.write_u8(expression_discriminant::SUM).await?;
+            write.write_u32(self.0).await?;
+            Box::pin(self.1.write_value(write)).await?;
+            Ok(())
+        }
+    }
+}
+
+impl<L: Expression> Expression for IsVariantExpression<L> {
+    type Target = bool;
+
+    fn write(
+        self,
+        write: &mut (impl AsyncWriteExt + Unpin + Send),
+    ) -> impl Future<Output = io::Result<()>> {
+        async move {
+            write.write_u8(expression_discriminant::IS_VARIANT).await?;
+            Box::pin(self.0.write(write)).await?;
+            write.write_u32(self.1).await?;
+            Ok(())
+        }
+    }
+}
+
+impl<L: Expression, A: MatchArms<Out>, Out: Schema> Expression for MatchExpression<L, A, Out> {
+    type Target = Out;
+
+    fn write(
+        self,
+        write: &mut (impl AsyncWriteExt + Unpin + Send),
+    ) -> impl Future<Output = io::Result<()>> {
+        async {
+            write.write_u8(expression_discriminant::MATCH).await?;
+            Box::pin(self.0.write(write)).await?;
+            write.write_u32(A::LENGTH).await?;
+            Box::pin(self.1.write_arms(write)).await?;
+            Ok(())
+        }
+    }
+}
+
+impl<L: Expression, K: Expression, M: Expression, Out: Schema + Send + Sync> Expression
+    for JoinExpression<L, K, M, Out>
+{
+    type Target = Vec<Out>;
+
+    fn write(
+        self,
+        write: &mut (impl AsyncWriteExt + Unpin + Send),
+    ) -> impl Future<Output = io::Result<()>> {
+        async {
+            write.write_u8(expression_discriminant::JOIN).await?;
+            Box::pin(self.0.write(write)).await?;
+            Box::pin(self.1.write(write)).await?;
+            Box::pin(self.2.write(write)).await?;
+            Ok(())
+        }
+    }
+}
+
+impl<L: Expression, C: Expression, I: Expression, Out: Schema + Send + Sync> Expression
+    for SlotMapPageExpression<L, C, I, Out>
+{
+    type Target = Vec<Out>;
+
+    fn write(
+        self,
+        write: &mut (impl AsyncWriteExt + Unpin + Send),
+    ) -> impl Future<Output = io::Result<()>> {
+        async {
+            write
+                .write_u8(expression_discriminant::SLOT_MAP_PAGE)
+                .await?;
+            Box::pin(self.0.write(write)).await?;
+            Box::pin(self.1.write(write)).await?;
+            Box::pin(self.2.write(write)).await?;
+            Ok(())
+        }
+    }
+}
+
+impl<L: Expression, K: Expression, Out: Schema + Send + Sync> Expression
+    for SlotMapRemoveExpression<L, K, Out>
+{
+    type Target = Option<Out>;
+
+    fn write(
+        self,
+        write: &mut (impl AsyncWriteExt + Unpin + Send),
+    ) -> impl Future<Output = io::Result<()>> {
+        async {
+            write
+                .write_u8(expression_discriminant::SLOT_MAP_REMOVE)
+                .await?;
+            Box::pin(self.0.write(write)).await?;
+            Box::pin(self.1.write(write)).await?;
+            Ok(())
+        }
+    }
+}
+
+impl<L: Expression, K: Expression, Out: Schema + Send + Sync> Expression
+    for MapGetExpression<L, K, Out>
+{
+    type Target = Option<Out>;
+
+    fn write(
+        self,
+        write: &mut (impl AsyncWriteExt + Unpin + Send),
+    ) -> impl Future<Output = io::Result<()>> {
+        async {
+            write.write_u8(expression_discriminant::MAP_GET).await?;
+            Box::pin(self.0.write(write)).await?;
+            Box::pin(self.1.write(write)).await?;
+            Ok(())
+        }
+    }
+}
+
+impl<L: Expression, K: Expression, V: Expression, Out: Schema + Send + Sync> Expression
+    for MapInsertExpression<L, K, V, Out>
+{
+    type Target = Option<Out>;
+
+    fn write(
+        self,
+        write: &mut (impl AsyncWriteExt + Unpin + Send),
+    ) -> impl Future<Output = io::Result<()>> {
+        async {
+            write.write_u8(expression_discriminant::MAP_INSERT).await?;
+            Box::pin(self.0.write(write)).await?;
+            Box::pin(self.1.write(write)).await?;
+            Box::pin(self.2.write(write)).await?;
+            Ok(())
+        }
+    }
+}
+
+impl<L: Expression, K: Expression, Out: Schema + Send + Sync> Expression
+    for MapRemoveExpression<L, K, Out>
+{
+    type Target = Option<Out>;
+
+    fn write(
+        self,
+        write: &mut (impl AsyncWriteExt + Unpin + Send),
+    ) -> impl Future<Output = io::Result<()>> {
+        async {
+            write.write_u8(expression_discriminant::MAP_REMOVE).await?;
+            Box::pin(self.0.write(write)).await?;
+            Box::pin(self.1.write(write)).await?;
+            Ok(())
+        }
+    }
+}
+
+impl<L: Expression, K: Expression> Expression for MapContainsKeyExpression<L, K> {
+    type Target = bool;
+
+    fn write(
+        self,
+        write: &mut (impl AsyncWriteExt + Unpin + Send),
+    ) -> impl Future<Output = io::Result<()>> {
+        async {
+            write
+                .write_u8(expression_discriminant::MAP_CONTAINS_KEY)
+                .await?;
+            Box::pin(self.0.write(write)).await?;
+            Box::pin(self.1.write(write)).await?;
+            Ok(())
+        }
+    }
+}
+
+impl<L: Expression, Out: Schema + Send + Sync> Expression for MapKeysExpression<L, Out> {
+    type Target = Vec<Out>;
+
+    fn write(
+        self,
+        write: &mut (impl AsyncWriteExt + Unpin + Send),
+    ) -> impl Future<Output = io::Result<()>> {
+        async {
+            write.write_u8(expression_discriminant::MAP_KEYS).await?;
+            Box::pin(self.0.write(write)).await?;
+            Ok(())
+        }
+    }
+}
+
+impl<L: Expression, Out: Schema + Send + Sync> Expression for MapValuesExpression<L, Out> {
+    type Target = Vec<Out>;
+
+    fn write(
+        self,
+        write: &mut (impl AsyncWriteExt + Unpin + Send),
+    ) -> impl Future<Output = io::Result<()>> {
+        async {
+            write.write_u8(expression_discriminant::MAP_VALUES).await?;
+            Box::pin(self.0.write(write)).await?;
+            Ok(())
+        }
+    }
+}
+
+impl<L: Expression, Out: Schema + Send + Sync> Expression for MapEntriesExpression<L, Out> {
+    type Target = Vec<Out>;
+
+    fn write(
+        self,
+        write: &mut (impl AsyncWriteExt + Unpin + Send),
+    ) -> impl Future<Output = io::Result<()>> {
+        async {
+            write.write_u8(expression_discriminant::MAP_ENTRIES).await?;
+            Box::pin(self.0.write(write)).await?;
+            Ok(())
+        }
+    }
+}
@@ -4,22 +4,39 @@ mod impls;
 mod node;
 mod operators;
 mod path;
+mod pattern;
+mod type_check;
 
 pub use self::{
     expression::Expression,
     impl_operators::{
-        AndExpression, ChainExpression, ConditionExpression, EqualExpression, FilterExpression,
-        FuseExpression, GetExpression, InsertExpression, LengthExpression, MapExpression,
-        MapVariantExpression, SetExpression,
+        AddExpression, AndExpression, AverageExpression, ChainExpression, ClearExpression,
+        ConcatExpression, ConditionExpression, CountExpression, DivExpression, EndsWithExpression,
+        EqualExpression, ExtendExpression, FilterExpression, FuseExpression, GetExpression,
+        GreaterEqualExpression, GreaterExpression, GroupByExpression, InsertExpression,
+        IsVariantExpression, JoinExpression, LengthExpression, LessEqualExpression, LessExpression,
+        LetExpression, ListSumExpression, MapContainsKeyExpression, MapEntriesExpression,
+        MapExpression, MapGetExpression, MapInsertExpression, MapKeysExpression,
+        MapRemoveExpression, MapValuesExpression, MapVariantExpression, MatchExpression,
+        MatchesExpression, MaxByExpression, MaxExpression, MinByExpression, MinExpression,
+        MulExpression, NotEqualExpression, NotExpression, OrExpression, PopExpression,
+        PushExpression, RemExpression, RemoveExpression, ReverseExpression, ReversedExpression,
+        SetContainsExpression, SetDifferenceExpression, SetExpression, SetInsertExpression,
+        SetIntersectionExpression, SetRemoveExpression, SetUnionExpression, SkipExpression,
+        SlotMapInsertExpression, SlotMapPageExpression, SlotMapRemoveExpression, SortExpression,
+        SortedExpression, StartsWithExpression, StringContainsExpression, StringLengthExpression,
+        SubExpression, SubstringExpression, TakeExpression, ToLowercaseExpression,
+        ToUppercaseExpression, TransformValuesExpression, TruncateExpression, VariantExpression,
+        XorExpression,
     },
     node::{expression_discriminant, ExpressionNode},
     operators::{
-        And, BoolOperators, Chain, FlattenOperator, HashSetFilter, Int128Equal, Int16Equal,
-        Int32Equal, Int64Equal, Int8Equal, Length, MapVec, NonZeroInt128Equal, NonZeroInt16Equal,
-        NonZeroInt32Equal, NonZeroInt64Equal, NonZeroInt8Equal, NonZeroUint128Equal,
-        NonZeroUint16Equal, NonZeroUint32Equal, NonZeroUint64Equal, NonZeroUint8Equal,
-        OptionOperators, Set, SetIfSome, SlotMapOperators, StringEqual, Uint128Equal, Uint16Equal,
-        Uint32Equal, Uint64Equal, Uint8Equal, VecFilter, VecGet, VecInsert,
+        is_variant, let_in, match_variants, variant, variant_arm, Aggregate, And, Arithmetic,
+        Ascending, BoolOperators, Chain, Compare, Descending, Equal, FlattenOperator, GroupBy,
+        HashMapOperators, HashSetFilter, HashSetOperators, Join, Length, MapVec, MatchArms,
+        Numeric, OptionOperators, Or, Ordered, Paginate, Set, SetIfSome, SlotMapOperators, Sort,
+        SortDirection, SortKey, StringOperators, VariantFields, VecFilter, VecGet, VecInsert,
+        VecOperators, Xor,
     },
     path::{
         FromPath, PathExpression, TupleExpression1, TupleExpression10, TupleExpression11,
@@ -27,4 +44,5 @@ pub use self::{
         TupleExpression16, TupleExpression2, TupleExpression3, TupleExpression4, TupleExpression5,
         TupleExpression6, TupleExpression7, TupleExpression8, TupleExpression9,
     },
+    pattern::{pattern_discriminant, CompiledPattern, Pattern},
 };
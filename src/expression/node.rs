@@ -1,11 +1,14 @@
 use std::{
-    io,
+    cmp::Ordering,
+    io, mem,
     sync::{Arc, Mutex},
 };
 
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 
-use crate::{io_error, SchemaNode, Value};
+use crate::{
+    io_error, CompiledPattern, Pattern, QueryError, SchemaNode, Value, ValueMap, ValueSet,
+};
 
 #[derive(Debug, Clone)]
 pub enum ExpressionNode {
@@ -26,6 +29,84 @@ pub enum ExpressionNode {
     Product(Vec<ExpressionNode>),
     Sum(Box<(u32, ExpressionNode)>),
     List(Vec<ExpressionNode>),
+    Add(Box<(ExpressionNode, ExpressionNode)>),
+    Sub(Box<(ExpressionNode, ExpressionNode)>),
+    Mul(Box<(ExpressionNode, ExpressionNode)>),
+    Div(Box<(ExpressionNode, ExpressionNode)>),
+    Rem(Box<(ExpressionNode, ExpressionNode)>),
+    Less(Box<(ExpressionNode, ExpressionNode)>),
+    LessEqual(Box<(ExpressionNode, ExpressionNode)>),
+    Greater(Box<(ExpressionNode, ExpressionNode)>),
+    GreaterEqual(Box<(ExpressionNode, ExpressionNode)>),
+    Or(Box<(ExpressionNode, ExpressionNode)>),
+    Not(Box<ExpressionNode>),
+    Xor(Box<(ExpressionNode, ExpressionNode)>),
+    NotEqual(Box<(ExpressionNode, ExpressionNode)>),
+    Push(Box<(ExpressionNode, ExpressionNode)>),
+    Pop(Box<ExpressionNode>),
+    Remove(Box<(ExpressionNode, ExpressionNode)>),
+    Clear(Box<ExpressionNode>),
+    Truncate(Box<(ExpressionNode, ExpressionNode)>),
+    Extend(Box<(ExpressionNode, ExpressionNode)>),
+    SlotMapInsert(Box<(ExpressionNode, ExpressionNode)>),
+    SlotMapRemove(Box<(ExpressionNode, ExpressionNode)>),
+    MapGet(Box<(ExpressionNode, ExpressionNode)>),
+    MapInsert(Box<(ExpressionNode, ExpressionNode, ExpressionNode)>),
+    MapRemove(Box<(ExpressionNode, ExpressionNode)>),
+    MapContainsKey(Box<(ExpressionNode, ExpressionNode)>),
+    MapKeys(Box<ExpressionNode>),
+    MapValues(Box<ExpressionNode>),
+    MapEntries(Box<ExpressionNode>),
+    SetContains(Box<(ExpressionNode, ExpressionNode)>),
+    SetInsert(Box<(ExpressionNode, ExpressionNode)>),
+    SetRemove(Box<(ExpressionNode, ExpressionNode)>),
+    SetUnion(Box<(ExpressionNode, ExpressionNode)>),
+    SetIntersection(Box<(ExpressionNode, ExpressionNode)>),
+    SetDifference(Box<(ExpressionNode, ExpressionNode)>),
+    Concat(Box<(ExpressionNode, ExpressionNode)>),
+    StringContains(Box<(ExpressionNode, ExpressionNode)>),
+    StartsWith(Box<(ExpressionNode, ExpressionNode)>),
+    EndsWith(Box<(ExpressionNode, ExpressionNode)>),
+    StringLength(Box<ExpressionNode>),
+    Substring(Box<(ExpressionNode, ExpressionNode, ExpressionNode)>),
+    ToLowercase(Box<ExpressionNode>),
+    ToUppercase(Box<ExpressionNode>),
+    Matches(Box<(ExpressionNode, CompiledPattern)>),
+    /// Sum of a list, the schema of the sum is given so that the sum of an
+    /// empty list has a type.
+    ListSum(Box<(ExpressionNode, SchemaNode)>),
+    Min(Box<ExpressionNode>),
+    Max(Box<ExpressionNode>),
+    Average(Box<ExpressionNode>),
+    MinBy(Box<(ExpressionNode, ExpressionNode)>),
+    MaxBy(Box<(ExpressionNode, ExpressionNode)>),
+    Count(Box<(ExpressionNode, ExpressionNode)>),
+    /// List and keys, each key being an expression evaluated in the scope of
+    /// an element and whether it is descending. Without keys the elements
+    /// themselves are sorted in ascending order.
+    Sorted(Box<(ExpressionNode, Vec<(ExpressionNode, bool)>)>),
+    Sort(Box<(ExpressionNode, Vec<(ExpressionNode, bool)>)>),
+    Reversed(Box<ExpressionNode>),
+    Reverse(Box<ExpressionNode>),
+    Skip(Box<(ExpressionNode, ExpressionNode)>),
+    Take(Box<(ExpressionNode, ExpressionNode)>),
+    /// Slot map, optional cursor key and maximum number of entries.
+    SlotMapPage(Box<(ExpressionNode, ExpressionNode, ExpressionNode)>),
+    GroupBy(Box<(ExpressionNode, ExpressionNode)>),
+    /// Map whose values are each given by an expression evaluated in the scope
+    /// of the value.
+    TransformValues(Box<(ExpressionNode, ExpressionNode)>),
+    /// List, key expression evaluated in the scope of an element and slot map
+    /// the keys are resolved in.
+    Join(Box<(ExpressionNode, ExpressionNode, ExpressionNode)>),
+    /// Sum and one arm per variant, the arm of the variant of the sum is
+    /// evaluated in the scope of its value.
+    Match(Box<(ExpressionNode, Vec<ExpressionNode>)>),
+    /// Whether a sum is of the variant with the given discriminant.
+    IsVariant(Box<(ExpressionNode, u32)>),
+    /// Value of the second expression, whose innermost scope is the value of
+    /// the first one.
+    Let(Box<(ExpressionNode, ExpressionNode)>),
 }
 
 pub mod expression_discriminant {
@@ -46,74 +127,165 @@ pub mod expression_discriminant {
     pub const PRODUCT: u8 = 14;
     pub const SUM: u8 = 15;
     pub const LIST: u8 = 16;
+    pub const ADD: u8 = 17;
+    pub const SUB: u8 = 18;
+    pub const MUL: u8 = 19;
+    pub const DIV: u8 = 20;
+    pub const REM: u8 = 21;
+    pub const LESS: u8 = 22;
+    pub const LESS_EQUAL: u8 = 23;
+    pub const GREATER: u8 = 24;
+    pub const GREATER_EQUAL: u8 = 25;
+    pub const OR: u8 = 26;
+    pub const NOT: u8 = 27;
+    pub const XOR: u8 = 28;
+    pub const NOT_EQUAL: u8 = 29;
+    pub const PUSH: u8 = 30;
+    pub const POP: u8 = 31;
+    pub const REMOVE: u8 = 32;
+    pub const CLEAR: u8 = 33;
+    pub const TRUNCATE: u8 = 34;
+    pub const EXTEND: u8 = 35;
+    pub const SLOT_MAP_INSERT: u8 = 36;
+    pub const SLOT_MAP_REMOVE: u8 = 37;
+    pub const MAP_GET: u8 = 38;
+    pub const MAP_INSERT: u8 = 39;
+    pub const MAP_REMOVE: u8 = 40;
+    pub const MAP_CONTAINS_KEY: u8 = 41;
+    pub const MAP_KEYS: u8 = 42;
+    pub const MAP_VALUES: u8 = 43;
+    pub const MAP_ENTRIES: u8 = 44;
+    pub const SET_CONTAINS: u8 = 45;
+    pub const SET_INSERT: u8 = 46;
+    pub const SET_REMOVE: u8 = 47;
+    pub const SET_UNION: u8 = 48;
+    pub const SET_INTERSECTION: u8 = 49;
+    pub const SET_DIFFERENCE: u8 = 50;
+    pub const CONCAT: u8 = 51;
+    pub const STRING_CONTAINS: u8 = 52;
+    pub const STARTS_WITH: u8 = 53;
+    pub const ENDS_WITH: u8 = 54;
+    pub const STRING_LENGTH: u8 = 55;
+    pub const SUBSTRING: u8 = 56;
+    pub const TO_LOWERCASE: u8 = 57;
+    pub const TO_UPPERCASE: u8 = 58;
+    pub const MATCHES: u8 = 59;
+    pub const LIST_SUM: u8 = 60;
+    pub const MIN: u8 = 61;
+    pub const MAX: u8 = 62;
+    pub const AVERAGE: u8 = 63;
+    pub const MIN_BY: u8 = 64;
+    pub const MAX_BY: u8 = 65;
+    pub const COUNT: u8 = 66;
+    pub const SORTED: u8 = 67;
+    pub const SORT: u8 = 68;
+    pub const REVERSED: u8 = 69;
+    pub const REVERSE: u8 = 70;
+    pub const SKIP: u8 = 71;
+    pub const TAKE: u8 = 72;
+    pub const SLOT_MAP_PAGE: u8 = 73;
+    pub const GROUP_BY: u8 = 74;
+    pub const TRANSFORM_VALUES: u8 = 75;
+    pub const JOIN: u8 = 76;
+    pub const MATCH: u8 = 77;
+    pub const IS_VARIANT: u8 = 78;
+    pub const LET: u8 = 79;
 }
 
 impl ExpressionNode {
-    pub fn evaluate(self, scopes: Vec<Arc<Mutex<Value>>>) -> Arc<Mutex<Value>> {
-        match self {
-            ExpressionNode::Path(path) => Value::scope_scopes(scopes, &path).unwrap(),
+    pub fn evaluate(self, scopes: Vec<Arc<Mutex<Value>>>) -> Result<Arc<Mutex<Value>>, QueryError> {
+        Ok(match self {
+            ExpressionNode::Path(path) => match Value::scope_scopes(scopes, &path) {
+                Some(value) => value,
+                None => return Err(QueryError::InvalidPath(path)),
+            },
             ExpressionNode::Value(_, value) => Arc::new(Mutex::new(value)),
             ExpressionNode::Set(operands) => {
                 let (left_expression, right_expression) = *operands;
 
-                let lhs = left_expression.evaluate(scopes.clone());
+                let lhs = left_expression.evaluate(scopes.clone())?;
 
                 let old_value = lhs.lock().unwrap().clone();
 
-                *lhs.lock().unwrap() = right_expression.evaluate(scopes).lock().unwrap().clone();
+                *lhs.lock().unwrap() = right_expression.evaluate(scopes)?.lock().unwrap().clone();
 
                 Arc::new(Mutex::new(old_value))
             }
             ExpressionNode::Equal(operands) => {
                 let (left_expression, right_expression) = *operands;
 
-                let left_value = left_expression.evaluate(scopes.clone());
-                let right_value = right_expression.evaluate(scopes);
+                let left_value = left_expression.evaluate(scopes.clone())?;
+                let right_value = right_expression.evaluate(scopes)?;
 
                 Arc::new(Mutex::new(Value::Boolean(
-                    left_value
-                        .clone()
-                        .lock()
-                        .unwrap()
-                        .equal(&right_value.clone().lock().unwrap()),
+                    Value::equal_shared(&left_value, &right_value)
+                        .ok_or(QueryError::InvalidOperand(expression_discriminant::EQUAL))?,
+                )))
+            }
+            ExpressionNode::NotEqual(operands) => {
+                let (left_expression, right_expression) = *operands;
+
+                let left_value = left_expression.evaluate(scopes.clone())?;
+                let right_value = right_expression.evaluate(scopes)?;
+
+                Arc::new(Mutex::new(Value::Boolean(
+                    !Value::equal_shared(&left_value, &right_value).ok_or(
+                        QueryError::InvalidOperand(expression_discriminant::NOT_EQUAL),
+                    )?,
                 )))
             }
             ExpressionNode::Filter(operands) => {
                 let (left_expression, right_expression) = *operands;
 
-                let left_value = left_expression.evaluate(scopes.clone());
+                let left_value = left_expression.evaluate(scopes.clone())?;
 
-                let Value::List(values) = &*left_value.lock().unwrap() else {
-                    panic!()
+                let keep = |value: &Arc<Mutex<Value>>| match *right_expression
+                    .clone()
+                    .evaluate(scopes.iter().cloned().chain([value.clone()]).collect())?
+                    .lock()
+                    .unwrap()
+                {
+                    Value::Boolean(keep) => Ok(keep),
+                    _ => Err(QueryError::InvalidOperand(expression_discriminant::FILTER)),
                 };
 
-                Arc::new(Mutex::new(Value::List(
-                    values
-                        .iter()
-                        .filter(|value| {
-                            match *right_expression
-                                .clone()
-                                .evaluate(
-                                    scopes.iter().cloned().chain([(**value).clone()]).collect(),
-                                )
-                                .lock()
-                                .unwrap()
-                            {
-                                Value::Boolean(keep) => keep,
-                                _ => panic!(),
+                let filtered_value = match &*left_value.lock().unwrap() {
+                    Value::List(values) => {
+                        let mut filtered_values = Vec::new();
+                        for value in values {
+                            if keep(value)? {
+                                filtered_values.push(value.clone());
                             }
-                        })
-                        .cloned()
-                        .collect(),
-                )))
+                        }
+
+                        Value::List(filtered_values)
+                    }
+                    Value::Set(values) => {
+                        let mut filtered_values = ValueSet::new();
+                        for value in values.iter() {
+                            // The predicate is given a copy of the element, an
+                            // element must never change once inserted.
+                            let value = value.lock().unwrap().deep_clone();
+
+                            if keep(&Arc::new(Mutex::new(value.deep_clone())))? {
+                                filtered_values.insert(value);
+                            }
+                        }
+
+                        Value::Set(filtered_values)
+                    }
+                    _ => return Err(QueryError::InvalidOperand(expression_discriminant::FILTER)),
+                };
+
+                Arc::new(Mutex::new(filtered_value))
             }
             ExpressionNode::Map(operands) => {
                 let (left_expression, right_expression) = *operands;
 
-                let left_value = left_expression.evaluate(scopes.clone());
+                let left_value = left_expression.evaluate(scopes.clone())?;
 
                 let Value::List(values) = &*left_value.lock().unwrap() else {
-                    panic!()
+                    return Err(QueryError::InvalidOperand(expression_discriminant::MAP));
                 };
 
                 Arc::new(Mutex::new(Value::List(
@@ -124,13 +296,13 @@ impl ExpressionNode {
                                 .clone()
                                 .evaluate(scopes.iter().cloned().chain([value.clone()]).collect())
                         })
-                        .collect(),
+                        .collect::<Result<_, _>>()?,
                 )))
             }
             ExpressionNode::Length(operand) => {
-                let value = operand.evaluate(scopes);
+                let value = operand.evaluate(scopes)?;
                 let Value::List(list) = &*value.lock().unwrap() else {
-                    panic!()
+                    return Err(QueryError::InvalidOperand(expression_discriminant::LENGTH));
                 };
 
                 Arc::new(Mutex::new(Value::Uint32(list.len().try_into().unwrap())))
@@ -139,23 +311,30 @@ impl ExpressionNode {
                 let (lhs, index, rhs) = *operands;
 
                 let index = {
-                    let rhs = index.evaluate(scopes.clone());
+                    let rhs = index.evaluate(scopes.clone())?;
                     let Value::Uint32(index) = &*rhs.lock().unwrap() else {
-                        panic!();
+                        return Err(QueryError::InvalidOperand(expression_discriminant::INSERT));
                     };
 
                     *index
                 };
 
-                let left_value = lhs.evaluate(scopes.clone());
-                let right_value = rhs.evaluate(scopes.clone());
+                let left_value = lhs.evaluate(scopes.clone())?;
+                let right_value = rhs.evaluate(scopes.clone())?;
 
                 {
                     let Value::List(list) = &mut *left_value.lock().unwrap() else {
-                        panic!();
+                        return Err(QueryError::InvalidOperand(expression_discriminant::INSERT));
                     };
 
-                    list.insert(index.try_into().unwrap(), right_value);
+                    if index as usize > list.len() {
+                        return Err(QueryError::IndexOutOfBounds {
+                            index,
+                            length: list.len().try_into().unwrap(),
+                        });
+                    }
+
+                    list.insert(index as usize, right_value);
                 }
 
                 left_value
@@ -163,41 +342,844 @@ impl ExpressionNode {
             ExpressionNode::And(operands) => {
                 let (left_expression, right_expression) = *operands;
 
-                let Value::Boolean(lhs) = *left_expression.evaluate(scopes.clone()).lock().unwrap()
+                // The right operand is only evaluated when the left one doesn't
+                // determine the result, as it may mutate the database.
+                let result = Self::evaluate_boolean(
+                    expression_discriminant::AND,
+                    left_expression,
+                    scopes.clone(),
+                )? && Self::evaluate_boolean(
+                    expression_discriminant::AND,
+                    right_expression,
+                    scopes,
+                )?;
+
+                Arc::new(Mutex::new(Value::Boolean(result)))
+            }
+            ExpressionNode::Or(operands) => {
+                let (left_expression, right_expression) = *operands;
+
+                // Short-circuits like `And`.
+                let result = Self::evaluate_boolean(
+                    expression_discriminant::OR,
+                    left_expression,
+                    scopes.clone(),
+                )? || Self::evaluate_boolean(
+                    expression_discriminant::OR,
+                    right_expression,
+                    scopes,
+                )?;
+
+                Arc::new(Mutex::new(Value::Boolean(result)))
+            }
+            ExpressionNode::Not(operand) => Arc::new(Mutex::new(Value::Boolean(
+                !Self::evaluate_boolean(expression_discriminant::NOT, *operand, scopes)?,
+            ))),
+            ExpressionNode::Push(operands) => {
+                let (list_expression, value_expression) = *operands;
+
+                let list = list_expression.evaluate(scopes.clone())?;
+                // The pushed value is copied so it doesn't share its children
+                // with the value it comes from.
+                let value = Arc::new(Mutex::new(
+                    value_expression
+                        .evaluate(scopes)?
+                        .lock()
+                        .unwrap()
+                        .deep_clone(),
+                ));
+
+                let Value::List(values) = &mut *list.lock().unwrap() else {
+                    return Err(QueryError::InvalidOperand(expression_discriminant::PUSH));
+                };
+
+                values.push(value);
+
+                Arc::new(Mutex::new(Value::Uint32(values.len().try_into().unwrap())))
+            }
+            ExpressionNode::Pop(operand) => {
+                let list = operand.evaluate(scopes)?;
+                let Value::List(values) = &mut *list.lock().unwrap() else {
+                    return Err(QueryError::InvalidOperand(expression_discriminant::POP));
+                };
+
+                Arc::new(Mutex::new(match values.pop() {
+                    Some(value) => Value::Sum(1, value),
+                    None => Value::Sum(0, Arc::new(Mutex::new(Value::Unit))),
+                }))
+            }
+            ExpressionNode::Remove(operands) => {
+                let (list_expression, index_expression) = *operands;
+
+                let index = {
+                    let index = index_expression.evaluate(scopes.clone())?;
+                    let Value::Uint32(index) = &*index.lock().unwrap() else {
+                        return Err(QueryError::InvalidOperand(expression_discriminant::REMOVE));
+                    };
+
+                    *index
+                };
+
+                let list = list_expression.evaluate(scopes)?;
+                let Value::List(values) = &mut *list.lock().unwrap() else {
+                    return Err(QueryError::InvalidOperand(expression_discriminant::REMOVE));
+                };
+
+                if index as usize >= values.len() {
+                    return Err(QueryError::IndexOutOfBounds {
+                        index,
+                        length: values.len().try_into().unwrap(),
+                    });
+                }
+
+                values.remove(index as usize)
+            }
+            ExpressionNode::Clear(operand) => {
+                let list = operand.evaluate(scopes)?;
+                let Value::List(values) = &mut *list.lock().unwrap() else {
+                    return Err(QueryError::InvalidOperand(expression_discriminant::CLEAR));
+                };
+
+                Arc::new(Mutex::new(Value::List(mem::take(values))))
+            }
+            ExpressionNode::Truncate(operands) => {
+                let (list_expression, length_expression) = *operands;
+
+                let length = {
+                    let length = length_expression.evaluate(scopes.clone())?;
+                    let Value::Uint32(length) = &*length.lock().unwrap() else {
+                        return Err(QueryError::InvalidOperand(
+                            expression_discriminant::TRUNCATE,
+                        ));
+                    };
+
+                    *length
+                };
+
+                let list = list_expression.evaluate(scopes)?;
+                let Value::List(values) = &mut *list.lock().unwrap() else {
+                    return Err(QueryError::InvalidOperand(
+                        expression_discriminant::TRUNCATE,
+                    ));
+                };
+
+                let length = values.len().min(length as usize);
+
+                Arc::new(Mutex::new(Value::List(values.split_off(length))))
+            }
+            ExpressionNode::Extend(operands) => {
+                let (list_expression, values_expression) = *operands;
+
+                let list = list_expression.evaluate(scopes.clone())?;
+                // Copied before locking the list, which may be extended with itself.
+                let Value::List(mut other_values) = values_expression
+                    .evaluate(scopes)?
+                    .lock()
+                    .unwrap()
+                    .deep_clone()
                 else {
-                    panic!();
+                    return Err(QueryError::InvalidOperand(expression_discriminant::EXTEND));
+                };
+
+                let Value::List(values) = &mut *list.lock().unwrap() else {
+                    return Err(QueryError::InvalidOperand(expression_discriminant::EXTEND));
+                };
+
+                values.append(&mut other_values);
+
+                Arc::new(Mutex::new(Value::Uint32(values.len().try_into().unwrap())))
+            }
+            ExpressionNode::SlotMapInsert(operands) => {
+                let (slot_map_expression, value_expression) = *operands;
+
+                let slot_map = slot_map_expression.evaluate(scopes.clone())?;
+                let value = value_expression
+                    .evaluate(scopes)?
+                    .lock()
+                    .unwrap()
+                    .deep_clone();
+
+                let (index, generation) = slot_map_insert(&slot_map.lock().unwrap(), value)?;
+
+                Arc::new(Mutex::new(Value::Product(vec![
+                    Arc::new(Mutex::new(Value::Uint32(index))),
+                    Arc::new(Mutex::new(Value::Uint32(generation))),
+                ])))
+            }
+            ExpressionNode::SlotMapRemove(operands) => {
+                let (slot_map_expression, key_expression) = *operands;
+
+                let (index, generation) = key_fields(
+                    &key_expression.evaluate(scopes.clone())?.lock().unwrap(),
+                    expression_discriminant::SLOT_MAP_REMOVE,
+                )?;
+
+                let slot_map = slot_map_expression.evaluate(scopes)?;
+                let removed = slot_map_remove(&slot_map.lock().unwrap(), index, generation)?;
+
+                Arc::new(Mutex::new(removed))
+            }
+            ExpressionNode::MapGet(operands) => {
+                let (map_expression, key_expression) = *operands;
+
+                let map = map_expression.evaluate(scopes.clone())?;
+                let key = key_expression.evaluate(scopes)?.lock().unwrap().clone();
+
+                let Value::Map(map) = &*map.lock().unwrap() else {
+                    return Err(QueryError::InvalidOperand(expression_discriminant::MAP_GET));
+                };
+
+                Arc::new(Mutex::new(match map.get(&key) {
+                    Some(value) => Value::Sum(1, value.clone()),
+                    None => Value::Sum(0, Arc::new(Mutex::new(Value::Unit))),
+                }))
+            }
+            ExpressionNode::MapInsert(operands) => {
+                let (map_expression, key_expression, value_expression) = *operands;
+
+                let map = map_expression.evaluate(scopes.clone())?;
+                // The entry is copied so it doesn't share its children with the
+                // values it comes from, a key must never change once inserted.
+                let key = key_expression
+                    .evaluate(scopes.clone())?
+                    .lock()
+                    .unwrap()
+                    .deep_clone();
+                let value = value_expression
+                    .evaluate(scopes)?
+                    .lock()
+                    .unwrap()
+                    .deep_clone();
+
+                let Value::Map(map) = &mut *map.lock().unwrap() else {
+                    return Err(QueryError::InvalidOperand(
+                        expression_discriminant::MAP_INSERT,
+                    ));
                 };
-                let Value::Boolean(rhs) =
-                    *right_expression.evaluate(scopes.clone()).lock().unwrap()
+
+                Arc::new(Mutex::new(
+                    match map.insert(key, Arc::new(Mutex::new(value))) {
+                        Some(value) => Value::Sum(1, value),
+                        None => Value::Sum(0, Arc::new(Mutex::new(Value::Unit))),
+                    },
+                ))
+            }
+            ExpressionNode::MapRemove(operands) => {
+                let (map_expression, key_expression) = *operands;
+
+                let map = map_expression.evaluate(scopes.clone())?;
+                let key = key_expression.evaluate(scopes)?.lock().unwrap().clone();
+
+                let Value::Map(map) = &mut *map.lock().unwrap() else {
+                    return Err(QueryError::InvalidOperand(
+                        expression_discriminant::MAP_REMOVE,
+                    ));
+                };
+
+                Arc::new(Mutex::new(match map.remove(&key) {
+                    Some(value) => Value::Sum(1, value),
+                    None => Value::Sum(0, Arc::new(Mutex::new(Value::Unit))),
+                }))
+            }
+            ExpressionNode::MapContainsKey(operands) => {
+                let (map_expression, key_expression) = *operands;
+
+                let map = map_expression.evaluate(scopes.clone())?;
+                let key = key_expression.evaluate(scopes)?.lock().unwrap().clone();
+
+                let Value::Map(map) = &*map.lock().unwrap() else {
+                    return Err(QueryError::InvalidOperand(
+                        expression_discriminant::MAP_CONTAINS_KEY,
+                    ));
+                };
+
+                Arc::new(Mutex::new(Value::Boolean(map.contains_key(&key))))
+            }
+            ExpressionNode::MapKeys(operand) => {
+                let map = operand.evaluate(scopes)?;
+                let Value::Map(map) = &*map.lock().unwrap() else {
+                    return Err(QueryError::InvalidOperand(
+                        expression_discriminant::MAP_KEYS,
+                    ));
+                };
+
+                // Keys are copied so the map can't be modified through them.
+                Arc::new(Mutex::new(Value::List(
+                    map.iter()
+                        .map(|(key, _)| Arc::new(Mutex::new(key.lock().unwrap().deep_clone())))
+                        .collect(),
+                )))
+            }
+            ExpressionNode::MapValues(operand) => {
+                let map = operand.evaluate(scopes)?;
+                let Value::Map(map) = &*map.lock().unwrap() else {
+                    return Err(QueryError::InvalidOperand(
+                        expression_discriminant::MAP_VALUES,
+                    ));
+                };
+
+                Arc::new(Mutex::new(Value::List(
+                    map.iter().map(|(_, value)| value.clone()).collect(),
+                )))
+            }
+            ExpressionNode::MapEntries(operand) => {
+                let map = operand.evaluate(scopes)?;
+                let Value::Map(map) = &*map.lock().unwrap() else {
+                    return Err(QueryError::InvalidOperand(
+                        expression_discriminant::MAP_ENTRIES,
+                    ));
+                };
+
+                Arc::new(Mutex::new(Value::List(
+                    map.iter()
+                        .map(|(key, value)| {
+                            Arc::new(Mutex::new(Value::Product(vec![
+                                Arc::new(Mutex::new(key.lock().unwrap().deep_clone())),
+                                value.clone(),
+                            ])))
+                        })
+                        .collect(),
+                )))
+            }
+            ExpressionNode::SetContains(operands) => {
+                let (set_expression, value_expression) = *operands;
+
+                let set = set_expression.evaluate(scopes.clone())?;
+                let value = value_expression.evaluate(scopes)?.lock().unwrap().clone();
+
+                let Value::Set(set) = &*set.lock().unwrap() else {
+                    return Err(QueryError::InvalidOperand(
+                        expression_discriminant::SET_CONTAINS,
+                    ));
+                };
+
+                Arc::new(Mutex::new(Value::Boolean(set.contains(&value))))
+            }
+            ExpressionNode::SetInsert(operands) => {
+                let (set_expression, value_expression) = *operands;
+
+                let set = set_expression.evaluate(scopes.clone())?;
+                // Same as map keys, elements must never change once inserted.
+                let value = value_expression
+                    .evaluate(scopes)?
+                    .lock()
+                    .unwrap()
+                    .deep_clone();
+
+                let Value::Set(set) = &mut *set.lock().unwrap() else {
+                    return Err(QueryError::InvalidOperand(
+                        expression_discriminant::SET_INSERT,
+                    ));
+                };
+
+                Arc::new(Mutex::new(Value::Boolean(set.insert(value))))
+            }
+            ExpressionNode::SetRemove(operands) => {
+                let (set_expression, value_expression) = *operands;
+
+                let set = set_expression.evaluate(scopes.clone())?;
+                let value = value_expression.evaluate(scopes)?.lock().unwrap().clone();
+
+                let Value::Set(set) = &mut *set.lock().unwrap() else {
+                    return Err(QueryError::InvalidOperand(
+                        expression_discriminant::SET_REMOVE,
+                    ));
+                };
+
+                Arc::new(Mutex::new(Value::Boolean(set.remove(&value))))
+            }
+            ExpressionNode::SetUnion(operands) => {
+                Self::evaluate_set_operation(expression_discriminant::SET_UNION, *operands, scopes)?
+            }
+            ExpressionNode::SetIntersection(operands) => Self::evaluate_set_operation(
+                expression_discriminant::SET_INTERSECTION,
+                *operands,
+                scopes,
+            )?,
+            ExpressionNode::SetDifference(operands) => Self::evaluate_set_operation(
+                expression_discriminant::SET_DIFFERENCE,
+                *operands,
+                scopes,
+            )?,
+            ExpressionNode::Concat(operands) => {
+                let (left_expression, right_expression) = *operands;
+
+                let mut lhs = Self::evaluate_string(
+                    expression_discriminant::CONCAT,
+                    left_expression,
+                    scopes.clone(),
+                )?;
+                let rhs = Self::evaluate_string(
+                    expression_discriminant::CONCAT,
+                    right_expression,
+                    scopes,
+                )?;
+
+                // A longer string couldn't be sent back.
+                if u32::try_from(lhs.len().saturating_add(rhs.len())).is_err() {
+                    return Err(QueryError::Overflow(expression_discriminant::CONCAT));
+                }
+
+                lhs.push_str(&rhs);
+
+                Arc::new(Mutex::new(Value::String(lhs)))
+            }
+            ExpressionNode::StringContains(operands) => Self::evaluate_string_predicate(
+                expression_discriminant::STRING_CONTAINS,
+                *operands,
+                scopes,
+            )?,
+            ExpressionNode::StartsWith(operands) => Self::evaluate_string_predicate(
+                expression_discriminant::STARTS_WITH,
+                *operands,
+                scopes,
+            )?,
+            ExpressionNode::EndsWith(operands) => Self::evaluate_string_predicate(
+                expression_discriminant::ENDS_WITH,
+                *operands,
+                scopes,
+            )?,
+            ExpressionNode::StringLength(operand) => {
+                let value = operand.evaluate(scopes)?;
+                let Value::String(string) = &*value.lock().unwrap() else {
+                    return Err(QueryError::InvalidOperand(
+                        expression_discriminant::STRING_LENGTH,
+                    ));
+                };
+
+                Arc::new(Mutex::new(Value::Uint32(
+                    string.chars().count().try_into().unwrap(),
+                )))
+            }
+            ExpressionNode::Substring(operands) => {
+                let (string_expression, start_expression, length_expression) = *operands;
+
+                let string = Self::evaluate_string(
+                    expression_discriminant::SUBSTRING,
+                    string_expression,
+                    scopes.clone(),
+                )?;
+                let Value::Uint32(start) =
+                    *start_expression.evaluate(scopes.clone())?.lock().unwrap()
+                else {
+                    return Err(QueryError::InvalidOperand(
+                        expression_discriminant::SUBSTRING,
+                    ));
+                };
+                let Value::Uint32(length) = *length_expression.evaluate(scopes)?.lock().unwrap()
+                else {
+                    return Err(QueryError::InvalidOperand(
+                        expression_discriminant::SUBSTRING,
+                    ));
+                };
+
+                Arc::new(Mutex::new(Value::String(
+                    string
+                        .chars()
+                        .skip(start as usize)
+                        .take(length as usize)
+                        .collect(),
+                )))
+            }
+            ExpressionNode::ToLowercase(operand) => Arc::new(Mutex::new(Value::String(
+                Self::evaluate_string(expression_discriminant::TO_LOWERCASE, *operand, scopes)?
+                    .to_lowercase(),
+            ))),
+            ExpressionNode::ToUppercase(operand) => Arc::new(Mutex::new(Value::String(
+                Self::evaluate_string(expression_discriminant::TO_UPPERCASE, *operand, scopes)?
+                    .to_uppercase(),
+            ))),
+            ExpressionNode::Matches(operands) => {
+                let (expression, pattern) = *operands;
+
+                let value = expression.evaluate(scopes)?;
+                let Value::String(string) = &*value.lock().unwrap() else {
+                    return Err(QueryError::InvalidOperand(expression_discriminant::MATCHES));
+                };
+
+                Arc::new(Mutex::new(Value::Boolean(
+                    pattern.regex()?.is_match(string),
+                )))
+            }
+            ExpressionNode::ListSum(operands) => {
+                let (list_expression, schema) = *operands;
+
+                let list = list_expression.evaluate(scopes)?;
+                let Value::List(values) = &*list.lock().unwrap() else {
+                    return Err(QueryError::InvalidOperand(
+                        expression_discriminant::LIST_SUM,
+                    ));
+                };
+
+                let mut sum = zero(&schema).ok_or(QueryError::InvalidOperand(
+                    expression_discriminant::LIST_SUM,
+                ))?;
+                for value in values {
+                    sum = arithmetic(expression_discriminant::ADD, &sum, &value.lock().unwrap())
+                        .map_err(|err| match err {
+                            QueryError::Overflow(_) => {
+                                QueryError::Overflow(expression_discriminant::LIST_SUM)
+                            }
+                            _ => QueryError::InvalidOperand(expression_discriminant::LIST_SUM),
+                        })?;
+                }
+
+                Arc::new(Mutex::new(sum))
+            }
+            ExpressionNode::Min(operand) => {
+                Self::evaluate_extremum(expression_discriminant::MIN, *operand, None, scopes)?
+            }
+            ExpressionNode::Max(operand) => {
+                Self::evaluate_extremum(expression_discriminant::MAX, *operand, None, scopes)?
+            }
+            ExpressionNode::MinBy(operands) => Self::evaluate_extremum(
+                expression_discriminant::MIN_BY,
+                operands.0,
+                Some(operands.1),
+                scopes,
+            )?,
+            ExpressionNode::MaxBy(operands) => Self::evaluate_extremum(
+                expression_discriminant::MAX_BY,
+                operands.0,
+                Some(operands.1),
+                scopes,
+            )?,
+            ExpressionNode::Average(operand) => {
+                let list = operand.evaluate(scopes)?;
+                let Value::List(values) = &*list.lock().unwrap() else {
+                    return Err(QueryError::InvalidOperand(expression_discriminant::AVERAGE));
+                };
+
+                let mut sum = 0.0;
+                for value in values {
+                    sum += to_f64(&value.lock().unwrap())
+                        .ok_or(QueryError::InvalidOperand(expression_discriminant::AVERAGE))?;
+                }
+
+                Arc::new(Mutex::new(if values.is_empty() {
+                    Value::Sum(0, Arc::new(Mutex::new(Value::Unit)))
+                } else {
+                    Value::Sum(
+                        1,
+                        Arc::new(Mutex::new(Value::Float64(sum / values.len() as f64))),
+                    )
+                }))
+            }
+            ExpressionNode::Count(operands) => {
+                let (list_expression, predicate) = *operands;
+
+                let list = list_expression.evaluate(scopes.clone())?;
+                // The list isn't locked while the predicate is evaluated, as
+                // it may refer to it.
+                let Value::List(values) = list.lock().unwrap().clone() else {
+                    return Err(QueryError::InvalidOperand(expression_discriminant::COUNT));
+                };
+
+                let mut count = 0u32;
+                for value in values {
+                    match *predicate
+                        .clone()
+                        .evaluate(scopes.iter().cloned().chain([value.clone()]).collect())?
+                        .lock()
+                        .unwrap()
+                    {
+                        Value::Boolean(true) => count += 1,
+                        Value::Boolean(false) => {}
+                        _ => {
+                            return Err(QueryError::InvalidOperand(expression_discriminant::COUNT))
+                        }
+                    }
+                }
+
+                Arc::new(Mutex::new(Value::Uint32(count)))
+            }
+            ExpressionNode::Sorted(operands) => {
+                let (list_expression, keys) = *operands;
+
+                let list = list_expression.evaluate(scopes.clone())?;
+                // The list isn't locked while keys are evaluated, as they may
+                // refer to it.
+                let Value::List(values) = list.lock().unwrap().clone() else {
+                    return Err(QueryError::InvalidOperand(expression_discriminant::SORTED));
+                };
+
+                Arc::new(Mutex::new(Value::List(Self::sort_values(
+                    expression_discriminant::SORTED,
+                    &values,
+                    &keys,
+                    &scopes,
+                )?)))
+            }
+            ExpressionNode::Sort(operands) => {
+                let (list_expression, keys) = *operands;
+
+                let list = list_expression.evaluate(scopes.clone())?;
+                // The list is only locked again to store the sorted elements,
+                // as keys may refer to it.
+                let Value::List(values) = list.lock().unwrap().clone() else {
+                    return Err(QueryError::InvalidOperand(expression_discriminant::SORT));
+                };
+
+                let sorted =
+                    Self::sort_values(expression_discriminant::SORT, &values, &keys, &scopes)?;
+
+                let Value::List(values) = &mut *list.lock().unwrap() else {
+                    return Err(QueryError::InvalidOperand(expression_discriminant::SORT));
+                };
+                *values = sorted;
+
+                Arc::new(Mutex::new(Value::Unit))
+            }
+            ExpressionNode::GroupBy(operands) => {
+                let (list_expression, key_expression) = *operands;
+
+                let list = list_expression.evaluate(scopes.clone())?;
+                // The list isn't locked while keys are evaluated, as they may
+                // refer to it.
+                let Value::List(values) = list.lock().unwrap().clone() else {
+                    return Err(QueryError::InvalidOperand(
+                        expression_discriminant::GROUP_BY,
+                    ));
+                };
+
+                let mut groups = ValueMap::new();
+                for value in values {
+                    // The key is copied so it doesn't share its children with
+                    // the element it comes from, a key must never change once
+                    // inserted.
+                    let key = key_expression
+                        .clone()
+                        .evaluate(scopes.iter().cloned().chain([value.clone()]).collect())?
+                        .lock()
+                        .unwrap()
+                        .deep_clone();
+
+                    match groups.get(&key) {
+                        Some(group) => {
+                            let Value::List(group) = &mut *group.lock().unwrap() else {
+                                unreachable!("group of group by expression isn't a list");
+                            };
+
+                            group.push(value.clone());
+                        }
+                        None => {
+                            groups.insert(
+                                key,
+                                Arc::new(Mutex::new(Value::List(vec![value.clone()]))),
+                            );
+                        }
+                    }
+                }
+
+                Arc::new(Mutex::new(Value::Map(groups)))
+            }
+            ExpressionNode::TransformValues(operands) => {
+                let (map_expression, value_expression) = *operands;
+
+                let map = map_expression.evaluate(scopes.clone())?;
+                // The map isn't locked while values are evaluated, as they may
+                // refer to it.
+                let entries = match &*map.lock().unwrap() {
+                    Value::Map(map) => map
+                        .iter()
+                        .map(|(key, value)| (key.lock().unwrap().deep_clone(), value.clone()))
+                        .collect::<Vec<_>>(),
+                    _ => {
+                        return Err(QueryError::InvalidOperand(
+                            expression_discriminant::TRANSFORM_VALUES,
+                        ))
+                    }
+                };
+
+                let mut transformed = ValueMap::new();
+                for (key, value) in entries {
+                    let value = value_expression
+                        .clone()
+                        .evaluate(scopes.iter().cloned().chain([value]).collect())?;
+
+                    transformed.insert(key, value);
+                }
+
+                Arc::new(Mutex::new(Value::Map(transformed)))
+            }
+            ExpressionNode::Join(operands) => {
+                let (list_expression, key_expression, slot_map_expression) = *operands;
+                let invalid_operand = || QueryError::InvalidOperand(expression_discriminant::JOIN);
+
+                let list = list_expression.evaluate(scopes.clone())?;
+                let Value::List(rows) = list.lock().unwrap().clone() else {
+                    return Err(invalid_operand());
+                };
+
+                // Keys are evaluated before the list or the slot map is
+                // locked, as they may refer to them.
+                let keys = rows
+                    .iter()
+                    .map(|row| {
+                        key_fields(
+                            &key_expression
+                                .clone()
+                                .evaluate(scopes.iter().cloned().chain([row.clone()]).collect())?
+                                .lock()
+                                .unwrap(),
+                            expression_discriminant::JOIN,
+                        )
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                let slot_map = slot_map_expression.evaluate(scopes)?;
+                let slot_map = slot_map.lock().unwrap();
+                let [slots, _] = pair(&slot_map, expression_discriminant::JOIN)?;
+                let slots = slots.lock().unwrap();
+                let Value::List(slots) = &*slots else {
+                    return Err(invalid_operand());
+                };
+
+                Arc::new(Mutex::new(Value::List(
+                    rows.iter()
+                        .zip(keys)
+                        .map(|(row, (index, generation))| {
+                            Ok(Arc::new(Mutex::new(Value::Product(vec![
+                                row.clone(),
+                                Arc::new(Mutex::new(slot_map_get(slots, index, generation)?)),
+                            ]))))
+                        })
+                        .collect::<Result<_, _>>()?,
+                )))
+            }
+            ExpressionNode::Skip(operands) => {
+                Self::evaluate_skip_take(expression_discriminant::SKIP, *operands, scopes)?
+            }
+            ExpressionNode::Take(operands) => {
+                Self::evaluate_skip_take(expression_discriminant::TAKE, *operands, scopes)?
+            }
+            ExpressionNode::SlotMapPage(operands) => {
+                let (slot_map_expression, cursor_expression, limit_expression) = *operands;
+
+                let cursor = match &*cursor_expression.evaluate(scopes.clone())?.lock().unwrap() {
+                    Value::Sum(0, _) => None,
+                    Value::Sum(1, key) => Some(key_fields(
+                        &key.lock().unwrap(),
+                        expression_discriminant::SLOT_MAP_PAGE,
+                    )?),
+                    _ => {
+                        return Err(QueryError::InvalidOperand(
+                            expression_discriminant::SLOT_MAP_PAGE,
+                        ))
+                    }
+                };
+                let Value::Uint32(limit) =
+                    *limit_expression.evaluate(scopes.clone())?.lock().unwrap()
                 else {
-                    panic!();
+                    return Err(QueryError::InvalidOperand(
+                        expression_discriminant::SLOT_MAP_PAGE,
+                    ));
                 };
 
-                Arc::new(Mutex::new(Value::Boolean(lhs && rhs)))
+                let slot_map = slot_map_expression.evaluate(scopes)?;
+                let entries = slot_map_page(&slot_map.lock().unwrap(), cursor, limit)?;
+
+                Arc::new(Mutex::new(Value::List(entries)))
+            }
+            ExpressionNode::Reversed(operand) => {
+                let list = operand.evaluate(scopes)?;
+                let Value::List(values) = &*list.lock().unwrap() else {
+                    return Err(QueryError::InvalidOperand(
+                        expression_discriminant::REVERSED,
+                    ));
+                };
+
+                Arc::new(Mutex::new(Value::List(
+                    values.iter().rev().cloned().collect(),
+                )))
+            }
+            ExpressionNode::Reverse(operand) => {
+                let list = operand.evaluate(scopes)?;
+                let Value::List(values) = &mut *list.lock().unwrap() else {
+                    return Err(QueryError::InvalidOperand(expression_discriminant::REVERSE));
+                };
+
+                values.reverse();
+
+                Arc::new(Mutex::new(Value::Unit))
+            }
+            ExpressionNode::Xor(operands) => {
+                let (left_expression, right_expression) = *operands;
+
+                let lhs = Self::evaluate_boolean(
+                    expression_discriminant::XOR,
+                    left_expression,
+                    scopes.clone(),
+                )?;
+                let rhs =
+                    Self::evaluate_boolean(expression_discriminant::XOR, right_expression, scopes)?;
+
+                Arc::new(Mutex::new(Value::Boolean(lhs != rhs)))
             }
             ExpressionNode::MapVariant(operands) => {
                 let (left_expression, target_discriminant, right_expression) = *operands;
 
-                let lhs = left_expression.evaluate(scopes.clone());
+                let lhs = left_expression.evaluate(scopes.clone())?;
 
                 let Value::Sum(discriminant, variant) = &*lhs.lock().unwrap() else {
-                    panic!();
+                    return Err(QueryError::InvalidOperand(
+                        expression_discriminant::MAP_VARIANT,
+                    ));
                 };
 
                 if *discriminant == target_discriminant {
                     Arc::new(Mutex::new(Value::Sum(
                         *discriminant,
                         right_expression
-                            .evaluate(scopes.iter().cloned().chain([variant.clone()]).collect()),
+                            .evaluate(scopes.iter().cloned().chain([variant.clone()]).collect())?,
                     )))
                 } else {
                     lhs.clone()
                 }
             }
+            ExpressionNode::Let(operands) => {
+                let (value_expression, body_expression) = *operands;
+
+                let value = value_expression.evaluate(scopes.clone())?;
+
+                body_expression.evaluate(scopes.into_iter().chain([value]).collect())?
+            }
+            ExpressionNode::IsVariant(operands) => {
+                let (sum_expression, target_discriminant) = *operands;
+
+                let Value::Sum(discriminant, _) = *sum_expression.evaluate(scopes)?.lock().unwrap()
+                else {
+                    return Err(QueryError::InvalidOperand(
+                        expression_discriminant::IS_VARIANT,
+                    ));
+                };
+
+                Arc::new(Mutex::new(Value::Boolean(
+                    discriminant == target_discriminant,
+                )))
+            }
+            ExpressionNode::Match(operands) => {
+                let (sum_expression, mut arms) = *operands;
+
+                let (discriminant, variant) =
+                    match &*sum_expression.evaluate(scopes.clone())?.lock().unwrap() {
+                        Value::Sum(discriminant, variant) => (*discriminant, variant.clone()),
+                        _ => {
+                            return Err(QueryError::InvalidOperand(expression_discriminant::MATCH))
+                        }
+                    };
+
+                if discriminant as usize >= arms.len() {
+                    return Err(QueryError::InvalidOperand(expression_discriminant::MATCH));
+                }
+
+                arms.swap_remove(discriminant as usize)
+                    .evaluate(scopes.into_iter().chain([variant]).collect())?
+            }
             ExpressionNode::Fuse(operand) => {
-                let value = operand.evaluate(scopes);
+                let value = operand.evaluate(scopes)?;
                 let Value::Sum(_, inner) = &*value.lock().unwrap() else {
-                    panic!()
+                    return Err(QueryError::InvalidOperand(expression_discriminant::FUSE));
                 };
 
                 inner.clone()
@@ -205,65 +1187,505 @@ impl ExpressionNode {
             ExpressionNode::Chain(operands) => {
                 let (left_expression, right_expression) = *operands;
 
-                left_expression.evaluate(scopes.clone());
-                right_expression.evaluate(scopes)
+                left_expression.evaluate(scopes.clone())?;
+                right_expression.evaluate(scopes)?
             }
             ExpressionNode::Get(operands) => {
                 let (left_expression, right_expression) = *operands;
 
                 let index = {
-                    let rhs = right_expression.evaluate(scopes.clone());
+                    let rhs = right_expression.evaluate(scopes.clone())?;
                     let Value::Uint32(index) = &*rhs.lock().unwrap() else {
-                        panic!();
+                        return Err(QueryError::InvalidOperand(expression_discriminant::GET));
                     };
 
                     *index
                 };
 
-                let lhs = left_expression.evaluate(scopes);
+                let lhs = left_expression.evaluate(scopes)?;
                 let lhs = lhs.lock().unwrap();
                 match &*lhs {
-                    Value::Product(product) => product[index as usize].clone(),
+                    Value::Product(product) => match product.get(index as usize) {
+                        Some(field) => field.clone(),
+                        None => {
+                            return Err(QueryError::IndexOutOfBounds {
+                                index,
+                                length: product.len().try_into().unwrap(),
+                            })
+                        }
+                    },
                     Value::List(list) => Arc::new(Mutex::new(match list.get(index as usize) {
                         Some(value) => Value::Sum(1, value.clone()),
                         None => Value::Sum(0, Arc::new(Mutex::new(Value::Unit))),
                     })),
-                    _ => panic!(),
+                    _ => return Err(QueryError::InvalidOperand(expression_discriminant::GET)),
                 }
             }
             ExpressionNode::Condition(operands) => {
                 let (condition, if_branch, else_branch) = *operands;
 
-                match &*condition.evaluate(scopes.clone()).lock().unwrap() {
-                    Value::Boolean(true) => if_branch.evaluate(scopes),
-                    Value::Boolean(false) => else_branch.evaluate(scopes),
-                    _ => panic!(),
+                let condition = match *condition.evaluate(scopes.clone())?.lock().unwrap() {
+                    Value::Boolean(condition) => condition,
+                    _ => {
+                        return Err(QueryError::InvalidOperand(
+                            expression_discriminant::CONDITION,
+                        ))
+                    }
+                };
+
+                if condition {
+                    if_branch.evaluate(scopes)?
+                } else {
+                    else_branch.evaluate(scopes)?
                 }
             }
             ExpressionNode::Product(fields) => Arc::new(Mutex::new(Value::Product(
                 fields
                     .into_iter()
                     .map(|field| field.evaluate(scopes.clone()))
-                    .collect(),
+                    .collect::<Result<_, _>>()?,
             ))),
             ExpressionNode::Sum(operands) => {
                 let (discriminant, expression) = *operands;
 
                 Arc::new(Mutex::new(Value::Sum(
                     discriminant,
-                    expression.evaluate(scopes),
+                    expression.evaluate(scopes)?,
                 )))
             }
             ExpressionNode::List(elements) => Arc::new(Mutex::new(Value::List(
                 elements
                     .into_iter()
                     .map(|element| element.evaluate(scopes.clone()))
-                    .collect(),
+                    .collect::<Result<_, _>>()?,
             ))),
+            ExpressionNode::Add(operands) => {
+                Self::evaluate_arithmetic(expression_discriminant::ADD, *operands, scopes)?
+            }
+            ExpressionNode::Sub(operands) => {
+                Self::evaluate_arithmetic(expression_discriminant::SUB, *operands, scopes)?
+            }
+            ExpressionNode::Mul(operands) => {
+                Self::evaluate_arithmetic(expression_discriminant::MUL, *operands, scopes)?
+            }
+            ExpressionNode::Div(operands) => {
+                Self::evaluate_arithmetic(expression_discriminant::DIV, *operands, scopes)?
+            }
+            ExpressionNode::Rem(operands) => {
+                Self::evaluate_arithmetic(expression_discriminant::REM, *operands, scopes)?
+            }
+            ExpressionNode::Less(operands) => {
+                Self::evaluate_comparison(expression_discriminant::LESS, *operands, scopes)?
+            }
+            ExpressionNode::LessEqual(operands) => {
+                Self::evaluate_comparison(expression_discriminant::LESS_EQUAL, *operands, scopes)?
+            }
+            ExpressionNode::Greater(operands) => {
+                Self::evaluate_comparison(expression_discriminant::GREATER, *operands, scopes)?
+            }
+            ExpressionNode::GreaterEqual(operands) => Self::evaluate_comparison(
+                expression_discriminant::GREATER_EQUAL,
+                *operands,
+                scopes,
+            )?,
+        })
+    }
+
+    fn evaluate_boolean(
+        discriminant: u8,
+        expression: ExpressionNode,
+        scopes: Vec<Arc<Mutex<Value>>>,
+    ) -> Result<bool, QueryError> {
+        match *expression.evaluate(scopes)?.lock().unwrap() {
+            Value::Boolean(value) => Ok(value),
+            _ => Err(QueryError::InvalidOperand(discriminant)),
+        }
+    }
+
+    /// Evaluate an expression to a string, the string is copied out of its
+    /// lock as other operands can be the same value.
+    fn evaluate_string(
+        discriminant: u8,
+        expression: ExpressionNode,
+        scopes: Vec<Arc<Mutex<Value>>>,
+    ) -> Result<String, QueryError> {
+        match &*expression.evaluate(scopes)?.lock().unwrap() {
+            Value::String(value) => Ok(value.clone()),
+            _ => Err(QueryError::InvalidOperand(discriminant)),
+        }
+    }
+
+    fn evaluate_string_predicate(
+        discriminant: u8,
+        (left_expression, right_expression): (ExpressionNode, ExpressionNode),
+        scopes: Vec<Arc<Mutex<Value>>>,
+    ) -> Result<Arc<Mutex<Value>>, QueryError> {
+        let lhs = Self::evaluate_string(discriminant, left_expression, scopes.clone())?;
+        let rhs = Self::evaluate_string(discriminant, right_expression, scopes)?;
+
+        Ok(Arc::new(Mutex::new(Value::Boolean(match discriminant {
+            expression_discriminant::STRING_CONTAINS => lhs.contains(&rhs),
+            expression_discriminant::STARTS_WITH => lhs.starts_with(&rhs),
+            expression_discriminant::ENDS_WITH => lhs.ends_with(&rhs),
+            _ => unreachable!("not a string predicate"),
+        }))))
+    }
+
+    /// Elements of a list after the first `count` ones, or the first `count`
+    /// ones.
+    fn evaluate_skip_take(
+        discriminant: u8,
+        (list_expression, count_expression): (ExpressionNode, ExpressionNode),
+        scopes: Vec<Arc<Mutex<Value>>>,
+    ) -> Result<Arc<Mutex<Value>>, QueryError> {
+        let Value::Uint32(count) = *count_expression.evaluate(scopes.clone())?.lock().unwrap()
+        else {
+            return Err(QueryError::InvalidOperand(discriminant));
+        };
+
+        let list = list_expression.evaluate(scopes)?;
+        let Value::List(values) = &*list.lock().unwrap() else {
+            return Err(QueryError::InvalidOperand(discriminant));
+        };
+
+        let values = values.iter().cloned();
+        let count = count as usize;
+
+        Ok(Arc::new(Mutex::new(Value::List(match discriminant {
+            expression_discriminant::SKIP => values.skip(count).collect(),
+            expression_discriminant::TAKE => values.take(count).collect(),
+            _ => unreachable!("not a skip or take expression"),
+        }))))
+    }
+
+    /// Elements of a list in the order of their keys, or of themselves if
+    /// there is no key. Values are ordered by [`Value::total_compare`] and the
+    /// sort is stable.
+    fn sort_values(
+        discriminant: u8,
+        values: &[Arc<Mutex<Value>>],
+        keys: &[(ExpressionNode, bool)],
+        scopes: &[Arc<Mutex<Value>>],
+    ) -> Result<Vec<Arc<Mutex<Value>>>, QueryError> {
+        let mut elements = Vec::new();
+        for value in values {
+            let element_keys = keys
+                .iter()
+                .map(|(key, _)| {
+                    key.clone()
+                        .evaluate(scopes.iter().cloned().chain([value.clone()]).collect())
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+
+            elements.push((element_keys, value.clone()));
+        }
+
+        let compare = |(lhs_keys, lhs): &(Vec<_>, _), (rhs_keys, rhs): &(Vec<_>, _)| {
+            if keys.is_empty() {
+                return Value::total_compare_shared(lhs, rhs);
+            }
+
+            for ((lhs_key, rhs_key), (_, descending)) in lhs_keys.iter().zip(rhs_keys).zip(keys) {
+                let ordering = Value::total_compare_shared(lhs_key, rhs_key)?;
+                let ordering = if *descending {
+                    ordering.reverse()
+                } else {
+                    ordering
+                };
+
+                if ordering.is_ne() {
+                    return Some(ordering);
+                }
+            }
+
+            Some(Ordering::Equal)
+        };
+
+        // The comparator can't fail, an incomparable pair is recorded and
+        // reported once the sort is done.
+        let mut incomparable = false;
+        elements.sort_by(|lhs, rhs| {
+            compare(lhs, rhs).unwrap_or_else(|| {
+                incomparable = true;
+                Ordering::Equal
+            })
+        });
+
+        if incomparable {
+            return Err(QueryError::InvalidOperand(discriminant));
         }
+
+        Ok(elements.into_iter().map(|(_, value)| value).collect())
+    }
+
+    async fn read_sort(
+        read: &mut (impl AsyncReadExt + Unpin),
+    ) -> io::Result<(Self, Vec<(Self, bool)>)> {
+        let list = Box::pin(Self::read(read)).await?;
+
+        let length: usize = read.read_u32().await?.try_into().map_err(|_| {
+            io_error!(
+                OutOfMemory,
+                "sort key count doesn't fit into a pointer sized unsigned integer",
+            )
+        })?;
+
+        let mut keys = Vec::new();
+        keys.try_reserve(length)
+            .map_err(|_| io_error!(OutOfMemory, "allocation of memory for sort keys failed"))?;
+
+        for _ in 0..length {
+            let descending = match read.read_u8().await? {
+                0 => false,
+                1 => true,
+                _ => {
+                    return Err(io_error!(
+                        InvalidData,
+                        "invalid direction while parsing sort key",
+                    ));
+                }
+            };
+
+            keys.push((Box::pin(Self::read(read)).await?, descending));
+        }
+
+        Ok((list, keys))
+    }
+
+    /// Least or greatest element of a list, or of its elements' keys if there
+    /// is a key expression, as an optional value. Values are ordered by
+    /// [`Value::total_compare`] and the first extreme element is kept.
+    fn evaluate_extremum(
+        discriminant: u8,
+        list_expression: ExpressionNode,
+        key_expression: Option<ExpressionNode>,
+        scopes: Vec<Arc<Mutex<Value>>>,
+    ) -> Result<Arc<Mutex<Value>>, QueryError> {
+        let invalid_operand = || QueryError::InvalidOperand(discriminant);
+        let wanted = match discriminant {
+            expression_discriminant::MIN | expression_discriminant::MIN_BY => Ordering::Less,
+            expression_discriminant::MAX | expression_discriminant::MAX_BY => Ordering::Greater,
+            _ => unreachable!("not an extremum expression"),
+        };
+
+        let list = list_expression.evaluate(scopes.clone())?;
+        // The list isn't locked while keys are evaluated, as they may refer to
+        // it.
+        let Value::List(values) = list.lock().unwrap().clone() else {
+            return Err(invalid_operand());
+        };
+
+        // Index and key of the extreme element.
+        let mut extremum: Option<(usize, Arc<Mutex<Value>>)> = None;
+        for (index, value) in values.iter().enumerate() {
+            let key = match &key_expression {
+                Some(key_expression) => key_expression
+                    .clone()
+                    .evaluate(scopes.iter().cloned().chain([value.clone()]).collect())?,
+                None => value.clone(),
+            };
+
+            let replace = match &extremum {
+                Some((_, extremum_key)) => {
+                    Value::total_compare_shared(&key, extremum_key).ok_or_else(invalid_operand)?
+                        == wanted
+                }
+                None => true,
+            };
+
+            if replace {
+                extremum = Some((index, key));
+            }
+        }
+
+        Ok(Arc::new(Mutex::new(match extremum {
+            Some((index, _)) => Value::Sum(1, values[index].clone()),
+            None => Value::Sum(0, Arc::new(Mutex::new(Value::Unit))),
+        })))
+    }
+
+    fn evaluate_arithmetic(
+        discriminant: u8,
+        (left_expression, right_expression): (ExpressionNode, ExpressionNode),
+        scopes: Vec<Arc<Mutex<Value>>>,
+    ) -> Result<Arc<Mutex<Value>>, QueryError> {
+        // The values are copied out of their locks as both operands can be the
+        // same value.
+        let lhs = left_expression
+            .evaluate(scopes.clone())?
+            .lock()
+            .unwrap()
+            .clone();
+        let rhs = right_expression.evaluate(scopes)?.lock().unwrap().clone();
+
+        Ok(Arc::new(Mutex::new(arithmetic(discriminant, &lhs, &rhs)?)))
+    }
+
+    fn evaluate_comparison(
+        discriminant: u8,
+        (left_expression, right_expression): (ExpressionNode, ExpressionNode),
+        scopes: Vec<Arc<Mutex<Value>>>,
+    ) -> Result<Arc<Mutex<Value>>, QueryError> {
+        let lhs = left_expression.evaluate(scopes.clone())?;
+        let rhs = right_expression.evaluate(scopes)?;
+
+        let ordering =
+            Value::compare_shared(&lhs, &rhs).ok_or(QueryError::InvalidOperand(discriminant))?;
+
+        // Unordered values, like a NaN float, are neither lesser, equal nor greater.
+        Ok(Arc::new(Mutex::new(Value::Boolean(match discriminant {
+            expression_discriminant::LESS => ordering.is_some_and(Ordering::is_lt),
+            expression_discriminant::LESS_EQUAL => ordering.is_some_and(Ordering::is_le),
+            expression_discriminant::GREATER => ordering.is_some_and(Ordering::is_gt),
+            expression_discriminant::GREATER_EQUAL => ordering.is_some_and(Ordering::is_ge),
+            _ => unreachable!("not a comparison expression"),
+        }))))
     }
 
-    fn discriminant(&self) -> u8 {
+    fn evaluate_set_operation(
+        discriminant: u8,
+        (left_expression, right_expression): (ExpressionNode, ExpressionNode),
+        scopes: Vec<Arc<Mutex<Value>>>,
+    ) -> Result<Arc<Mutex<Value>>, QueryError> {
+        // The sets are copied out of their locks as both operands can be the
+        // same value, their elements are still shared.
+        let Value::Set(lhs) = left_expression
+            .evaluate(scopes.clone())?
+            .lock()
+            .unwrap()
+            .clone()
+        else {
+            return Err(QueryError::InvalidOperand(discriminant));
+        };
+        let Value::Set(rhs) = right_expression.evaluate(scopes)?.lock().unwrap().clone() else {
+            return Err(QueryError::InvalidOperand(discriminant));
+        };
+
+        let result = match discriminant {
+            expression_discriminant::SET_UNION => {
+                let mut union = lhs;
+                for element in rhs.iter() {
+                    union.insert(element.lock().unwrap().clone());
+                }
+
+                union
+            }
+            expression_discriminant::SET_INTERSECTION | expression_discriminant::SET_DIFFERENCE => {
+                let keep_contained = discriminant == expression_discriminant::SET_INTERSECTION;
+
+                let mut result = ValueSet::new();
+                for element in lhs.iter() {
+                    let element = element.lock().unwrap().clone();
+
+                    if rhs.contains(&element) == keep_contained {
+                        result.insert(element);
+                    }
+                }
+
+                result
+            }
+            _ => unreachable!("not a set operation"),
+        };
+
+        Ok(Arc::new(Mutex::new(Value::Set(result))))
+    }
+
+    /// Whether evaluating this expression may modify the values it operates on.
+    pub fn is_mutating(&self) -> bool {
+        match self {
+            ExpressionNode::Set(_)
+            | ExpressionNode::Insert(_)
+            | ExpressionNode::Push(_)
+            | ExpressionNode::Pop(_)
+            | ExpressionNode::Remove(_)
+            | ExpressionNode::Clear(_)
+            | ExpressionNode::Truncate(_)
+            | ExpressionNode::Extend(_)
+            | ExpressionNode::SlotMapInsert(_)
+            | ExpressionNode::SlotMapRemove(_)
+            | ExpressionNode::MapInsert(_)
+            | ExpressionNode::MapRemove(_)
+            | ExpressionNode::SetInsert(_)
+            | ExpressionNode::SetRemove(_) => true,
+            ExpressionNode::Path(_) | ExpressionNode::Value(_, _) => false,
+            ExpressionNode::Length(operand)
+            | ExpressionNode::Fuse(operand)
+            | ExpressionNode::Not(operand)
+            | ExpressionNode::MapKeys(operand)
+            | ExpressionNode::MapValues(operand)
+            | ExpressionNode::MapEntries(operand)
+            | ExpressionNode::StringLength(operand)
+            | ExpressionNode::ToLowercase(operand)
+            | ExpressionNode::ToUppercase(operand)
+            | ExpressionNode::Min(operand)
+            | ExpressionNode::Max(operand)
+            | ExpressionNode::Average(operand)
+            | ExpressionNode::Reversed(operand) => operand.is_mutating(),
+            ExpressionNode::Sort(_) | ExpressionNode::Reverse(_) => true,
+            ExpressionNode::Sorted(operands) => {
+                operands.0.is_mutating() || operands.1.iter().any(|(key, _)| key.is_mutating())
+            }
+            ExpressionNode::Match(operands) => {
+                operands.0.is_mutating() || operands.1.iter().any(ExpressionNode::is_mutating)
+            }
+            ExpressionNode::Matches(operands) => operands.0.is_mutating(),
+            ExpressionNode::IsVariant(operands) => operands.0.is_mutating(),
+            ExpressionNode::ListSum(operands) => operands.0.is_mutating(),
+            ExpressionNode::Equal(operands)
+            | ExpressionNode::NotEqual(operands)
+            | ExpressionNode::Filter(operands)
+            | ExpressionNode::Map(operands)
+            | ExpressionNode::Let(operands)
+            | ExpressionNode::And(operands)
+            | ExpressionNode::Chain(operands)
+            | ExpressionNode::Get(operands)
+            | ExpressionNode::Add(operands)
+            | ExpressionNode::Sub(operands)
+            | ExpressionNode::Mul(operands)
+            | ExpressionNode::Div(operands)
+            | ExpressionNode::Rem(operands)
+            | ExpressionNode::Less(operands)
+            | ExpressionNode::LessEqual(operands)
+            | ExpressionNode::Greater(operands)
+            | ExpressionNode::GreaterEqual(operands)
+            | ExpressionNode::Or(operands)
+            | ExpressionNode::Xor(operands)
+            | ExpressionNode::MapGet(operands)
+            | ExpressionNode::MapContainsKey(operands)
+            | ExpressionNode::SetContains(operands)
+            | ExpressionNode::SetUnion(operands)
+            | ExpressionNode::SetIntersection(operands)
+            | ExpressionNode::SetDifference(operands)
+            | ExpressionNode::Concat(operands)
+            | ExpressionNode::StringContains(operands)
+            | ExpressionNode::StartsWith(operands)
+            | ExpressionNode::EndsWith(operands)
+            | ExpressionNode::MinBy(operands)
+            | ExpressionNode::MaxBy(operands)
+            | ExpressionNode::Count(operands)
+            | ExpressionNode::Skip(operands)
+            | ExpressionNode::Take(operands)
+            | ExpressionNode::GroupBy(operands)
+            | ExpressionNode::TransformValues(operands) => {
+                operands.0.is_mutating() || operands.1.is_mutating()
+            }
+            ExpressionNode::MapVariant(operands) => {
+                operands.0.is_mutating() || operands.2.is_mutating()
+            }
+            ExpressionNode::Condition(operands)
+            | ExpressionNode::Substring(operands)
+            | ExpressionNode::SlotMapPage(operands)
+            | ExpressionNode::Join(operands) => {
+                operands.0.is_mutating() || operands.1.is_mutating() || operands.2.is_mutating()
+            }
+            ExpressionNode::Sum(operands) => operands.1.is_mutating(),
+            ExpressionNode::Product(expressions) | ExpressionNode::List(expressions) => {
+                expressions.iter().any(ExpressionNode::is_mutating)
+            }
+        }
+    }
+
+    pub(crate) fn discriminant(&self) -> u8 {
         match self {
             ExpressionNode::Path(_) => expression_discriminant::PATH,
             ExpressionNode::Value(_, _) => expression_discriminant::VALUE,
@@ -282,6 +1704,69 @@ impl ExpressionNode {
             ExpressionNode::Product(_) => expression_discriminant::PRODUCT,
             ExpressionNode::Sum(_) => expression_discriminant::SUM,
             ExpressionNode::List(_) => expression_discriminant::LIST,
+            ExpressionNode::Add(_) => expression_discriminant::ADD,
+            ExpressionNode::Sub(_) => expression_discriminant::SUB,
+            ExpressionNode::Mul(_) => expression_discriminant::MUL,
+            ExpressionNode::Div(_) => expression_discriminant::DIV,
+            ExpressionNode::Rem(_) => expression_discriminant::REM,
+            ExpressionNode::Less(_) => expression_discriminant::LESS,
+            ExpressionNode::LessEqual(_) => expression_discriminant::LESS_EQUAL,
+            ExpressionNode::Greater(_) => expression_discriminant::GREATER,
+            ExpressionNode::GreaterEqual(_) => expression_discriminant::GREATER_EQUAL,
+            ExpressionNode::Or(_) => expression_discriminant::OR,
+            ExpressionNode::Not(_) => expression_discriminant::NOT,
+            ExpressionNode::Xor(_) => expression_discriminant::XOR,
+            ExpressionNode::NotEqual(_) => expression_discriminant::NOT_EQUAL,
+            ExpressionNode::Push(_) => expression_discriminant::PUSH,
+            ExpressionNode::Pop(_) => expression_discriminant::POP,
+            ExpressionNode::Remove(_) => expression_discriminant::REMOVE,
+            ExpressionNode::Clear(_) => expression_discriminant::CLEAR,
+            ExpressionNode::Truncate(_) => expression_discriminant::TRUNCATE,
+            ExpressionNode::Extend(_) => expression_discriminant::EXTEND,
+            ExpressionNode::SlotMapInsert(_) => expression_discriminant::SLOT_MAP_INSERT,
+            ExpressionNode::SlotMapRemove(_) => expression_discriminant::SLOT_MAP_REMOVE,
+            ExpressionNode::MapGet(_) => expression_discriminant::MAP_GET,
+            ExpressionNode::MapInsert(_) => expression_discriminant::MAP_INSERT,
+            ExpressionNode::MapRemove(_) => expression_discriminant::MAP_REMOVE,
+            ExpressionNode::MapContainsKey(_) => expression_discriminant::MAP_CONTAINS_KEY,
+            ExpressionNode::MapKeys(_) => expression_discriminant::MAP_KEYS,
+            ExpressionNode::MapValues(_) => expression_discriminant::MAP_VALUES,
+            ExpressionNode::MapEntries(_) => expression_discriminant::MAP_ENTRIES,
+            ExpressionNode::SetContains(_) => expression_discriminant::SET_CONTAINS,
+            ExpressionNode::SetInsert(_) => expression_discriminant::SET_INSERT,
+            ExpressionNode::SetRemove(_) => expression_discriminant::SET_REMOVE,
+            ExpressionNode::SetUnion(_) => expression_discriminant::SET_UNION,
+            ExpressionNode::SetIntersection(_) => expression_discriminant::SET_INTERSECTION,
+            ExpressionNode::SetDifference(_) => expression_discriminant::SET_DIFFERENCE,
+            ExpressionNode::Concat(_) => expression_discriminant::CONCAT,
+            ExpressionNode::StringContains(_) => expression_discriminant::STRING_CONTAINS,
+            ExpressionNode::StartsWith(_) => expression_discriminant::STARTS_WITH,
+            ExpressionNode::EndsWith(_) => expression_discriminant::ENDS_WITH,
+            ExpressionNode::StringLength(_) => expression_discriminant::STRING_LENGTH,
+            ExpressionNode::Substring(_) => expression_discriminant::SUBSTRING,
+            ExpressionNode::ToLowercase(_) => expression_discriminant::TO_LOWERCASE,
+            ExpressionNode::ToUppercase(_) => expression_discriminant::TO_UPPERCASE,
+            ExpressionNode::Matches(_) => expression_discriminant::MATCHES,
+            ExpressionNode::ListSum(_) => expression_discriminant::LIST_SUM,
+            ExpressionNode::Min(_) => expression_discriminant::MIN,
+            ExpressionNode::Max(_) => expression_discriminant::MAX,
+            ExpressionNode::Average(_) => expression_discriminant::AVERAGE,
+            ExpressionNode::MinBy(_) => expression_discriminant::MIN_BY,
+            ExpressionNode::MaxBy(_) => expression_discriminant::MAX_BY,
+            ExpressionNode::Count(_) => expression_discriminant::COUNT,
+            ExpressionNode::Sorted(_) => expression_discriminant::SORTED,
+            ExpressionNode::Sort(_) => expression_discriminant::SORT,
+            ExpressionNode::Reversed(_) => expression_discriminant::REVERSED,
+            ExpressionNode::Reverse(_) => expression_discriminant::REVERSE,
+            ExpressionNode::Skip(_) => expression_discriminant::SKIP,
+            ExpressionNode::Take(_) => expression_discriminant::TAKE,
+            ExpressionNode::SlotMapPage(_) => expression_discriminant::SLOT_MAP_PAGE,
+            ExpressionNode::GroupBy(_) => expression_discriminant::GROUP_BY,
+            ExpressionNode::TransformValues(_) => expression_discriminant::TRANSFORM_VALUES,
+            ExpressionNode::Join(_) => expression_discriminant::JOIN,
+            ExpressionNode::Match(_) => expression_discriminant::MATCH,
+            ExpressionNode::IsVariant(_) => expression_discriminant::IS_VARIANT,
+            ExpressionNode::Let(_) => expression_discriminant::LET,
         }
     }
 
@@ -316,103 +1801,355 @@ impl ExpressionNode {
                 let value = Value::read(&schema, read).await?;
                 Self::Value(schema, value)
             }
-            expression_discriminant::SET => Self::Set(Box::new((
+            expression_discriminant::SET => Self::Set(Box::new((
+                Box::pin(Self::read(read)).await?,
+                Box::pin(Self::read(read)).await?,
+            ))),
+            expression_discriminant::EQUAL => Self::Equal(Box::new((
+                Box::pin(Self::read(read)).await?,
+                Box::pin(Self::read(read)).await?,
+            ))),
+            expression_discriminant::FILTER => Self::Filter(Box::new((
+                Box::pin(Self::read(read)).await?,
+                Box::pin(Self::read(read)).await?,
+            ))),
+            expression_discriminant::MAP => Self::Map(Box::new((
+                Box::pin(Self::read(read)).await?,
+                Box::pin(Self::read(read)).await?,
+            ))),
+            expression_discriminant::LENGTH => {
+                Self::Length(Box::new(Box::pin(Self::read(read)).await?))
+            }
+            expression_discriminant::INSERT => Self::Insert(Box::new((
+                Box::pin(Self::read(read)).await?,
+                Box::pin(Self::read(read)).await?,
+                Box::pin(Self::read(read)).await?,
+            ))),
+            expression_discriminant::AND => Self::And(Box::new((
+                Box::pin(Self::read(read)).await?,
+                Box::pin(Self::read(read)).await?,
+            ))),
+            expression_discriminant::MAP_VARIANT => Self::MapVariant(Box::new((
+                Box::pin(Self::read(read)).await?,
+                read.read_u32().await?,
+                Box::pin(Self::read(read)).await?,
+            ))),
+            expression_discriminant::FUSE => {
+                Self::Fuse(Box::new(Box::pin(Self::read(read)).await?))
+            }
+            expression_discriminant::CHAIN => Self::Chain(Box::new((
+                Box::pin(Self::read(read)).await?,
+                Box::pin(Self::read(read)).await?,
+            ))),
+            expression_discriminant::GET => Self::Get(Box::new((
+                Box::pin(Self::read(read)).await?,
+                Box::pin(Self::read(read)).await?,
+            ))),
+            expression_discriminant::CONDITION => Self::Condition(Box::new((
+                Box::pin(Self::read(read)).await?,
+                Box::pin(Self::read(read)).await?,
+                Box::pin(Self::read(read)).await?,
+            ))),
+            expression_discriminant::PRODUCT => {
+                let length: usize = read.read_u32().await?.try_into().map_err(|_| {
+                    io_error!(
+                        OutOfMemory,
+                        "product expression length doesn't fit into a pointer sized unsigned integer",
+                    )
+                })?;
+
+                let mut fields = Vec::new();
+                fields.try_reserve(length).map_err(|_| {
+                    io_error!(
+                        OutOfMemory,
+                        "allocation of memory for product expression failed"
+                    )
+                })?;
+
+                for _ in 0..length {
+                    fields.push(Box::pin(Self::read(read)).await?);
+                }
+
+                Self::Product(fields)
+            }
+            expression_discriminant::SUM => Self::Sum(Box::new((
+                read.read_u32().await?,
+                Box::pin(Self::read(read)).await?,
+            ))),
+            expression_discriminant::LIST => {
+                let length: usize = read.read_u32().await?.try_into().map_err(|_| {
+                    io_error!(
+                        OutOfMemory,
+                        "list expression length doesn't fit into a pointer sized unsigned integer",
+                    )
+                })?;
+
+                let mut elements = Vec::new();
+                elements.try_reserve(length).map_err(|_| {
+                    io_error!(
+                        OutOfMemory,
+                        "allocation of memory for list expression failed"
+                    )
+                })?;
+
+                for _ in 0..length {
+                    elements.push(Box::pin(Self::read(read)).await?);
+                }
+
+                Self::List(elements)
+            }
+            expression_discriminant::ADD => Self::Add(Box::new((
+                Box::pin(Self::read(read)).await?,
+                Box::pin(Self::read(read)).await?,
+            ))),
+            expression_discriminant::SUB => Self::Sub(Box::new((
+                Box::pin(Self::read(read)).await?,
+                Box::pin(Self::read(read)).await?,
+            ))),
+            expression_discriminant::MUL => Self::Mul(Box::new((
+                Box::pin(Self::read(read)).await?,
+                Box::pin(Self::read(read)).await?,
+            ))),
+            expression_discriminant::DIV => Self::Div(Box::new((
+                Box::pin(Self::read(read)).await?,
+                Box::pin(Self::read(read)).await?,
+            ))),
+            expression_discriminant::REM => Self::Rem(Box::new((
+                Box::pin(Self::read(read)).await?,
+                Box::pin(Self::read(read)).await?,
+            ))),
+            expression_discriminant::LESS => Self::Less(Box::new((
+                Box::pin(Self::read(read)).await?,
+                Box::pin(Self::read(read)).await?,
+            ))),
+            expression_discriminant::LESS_EQUAL => Self::LessEqual(Box::new((
+                Box::pin(Self::read(read)).await?,
+                Box::pin(Self::read(read)).await?,
+            ))),
+            expression_discriminant::GREATER => Self::Greater(Box::new((
+                Box::pin(Self::read(read)).await?,
+                Box::pin(Self::read(read)).await?,
+            ))),
+            expression_discriminant::GREATER_EQUAL => Self::GreaterEqual(Box::new((
+                Box::pin(Self::read(read)).await?,
+                Box::pin(Self::read(read)).await?,
+            ))),
+            expression_discriminant::OR => Self::Or(Box::new((
+                Box::pin(Self::read(read)).await?,
+                Box::pin(Self::read(read)).await?,
+            ))),
+            expression_discriminant::NOT => Self::Not(Box::new(Box::pin(Self::read(read)).await?)),
+            expression_discriminant::XOR => Self::Xor(Box::new((
+                Box::pin(Self::read(read)).await?,
+                Box::pin(Self::read(read)).await?,
+            ))),
+            expression_discriminant::NOT_EQUAL => Self::NotEqual(Box::new((
+                Box::pin(Self::read(read)).await?,
+                Box::pin(Self::read(read)).await?,
+            ))),
+            expression_discriminant::PUSH => Self::Push(Box::new((
+                Box::pin(Self::read(read)).await?,
+                Box::pin(Self::read(read)).await?,
+            ))),
+            expression_discriminant::POP => Self::Pop(Box::new(Box::pin(Self::read(read)).await?)),
+            expression_discriminant::REMOVE => Self::Remove(Box::new((
+                Box::pin(Self::read(read)).await?,
+                Box::pin(Self::read(read)).await?,
+            ))),
+            expression_discriminant::CLEAR => {
+                Self::Clear(Box::new(Box::pin(Self::read(read)).await?))
+            }
+            expression_discriminant::TRUNCATE => Self::Truncate(Box::new((
                 Box::pin(Self::read(read)).await?,
                 Box::pin(Self::read(read)).await?,
             ))),
-            expression_discriminant::EQUAL => Self::Equal(Box::new((
+            expression_discriminant::EXTEND => Self::Extend(Box::new((
                 Box::pin(Self::read(read)).await?,
                 Box::pin(Self::read(read)).await?,
             ))),
-            expression_discriminant::FILTER => Self::Filter(Box::new((
+            expression_discriminant::SLOT_MAP_INSERT => Self::SlotMapInsert(Box::new((
                 Box::pin(Self::read(read)).await?,
                 Box::pin(Self::read(read)).await?,
             ))),
-            expression_discriminant::MAP => Self::Map(Box::new((
+            expression_discriminant::SLOT_MAP_REMOVE => Self::SlotMapRemove(Box::new((
                 Box::pin(Self::read(read)).await?,
                 Box::pin(Self::read(read)).await?,
             ))),
-            expression_discriminant::LENGTH => {
-                Self::Length(Box::new(Box::pin(Self::read(read)).await?))
+            expression_discriminant::MAP_GET => Self::MapGet(Box::new((
+                Box::pin(Self::read(read)).await?,
+                Box::pin(Self::read(read)).await?,
+            ))),
+            expression_discriminant::MAP_INSERT => Self::MapInsert(Box::new((
+                Box::pin(Self::read(read)).await?,
+                Box::pin(Self::read(read)).await?,
+                Box::pin(Self::read(read)).await?,
+            ))),
+            expression_discriminant::MAP_REMOVE => Self::MapRemove(Box::new((
+                Box::pin(Self::read(read)).await?,
+                Box::pin(Self::read(read)).await?,
+            ))),
+            expression_discriminant::MAP_CONTAINS_KEY => Self::MapContainsKey(Box::new((
+                Box::pin(Self::read(read)).await?,
+                Box::pin(Self::read(read)).await?,
+            ))),
+            expression_discriminant::MAP_KEYS => {
+                Self::MapKeys(Box::new(Box::pin(Self::read(read)).await?))
             }
-            expression_discriminant::INSERT => Self::Insert(Box::new((
+            expression_discriminant::MAP_VALUES => {
+                Self::MapValues(Box::new(Box::pin(Self::read(read)).await?))
+            }
+            expression_discriminant::MAP_ENTRIES => {
+                Self::MapEntries(Box::new(Box::pin(Self::read(read)).await?))
+            }
+            expression_discriminant::SET_CONTAINS => Self::SetContains(Box::new((
                 Box::pin(Self::read(read)).await?,
                 Box::pin(Self::read(read)).await?,
+            ))),
+            expression_discriminant::SET_INSERT => Self::SetInsert(Box::new((
+                Box::pin(Self::read(read)).await?,
                 Box::pin(Self::read(read)).await?,
             ))),
-            expression_discriminant::AND => Self::And(Box::new((
+            expression_discriminant::SET_REMOVE => Self::SetRemove(Box::new((
                 Box::pin(Self::read(read)).await?,
                 Box::pin(Self::read(read)).await?,
             ))),
-            expression_discriminant::MAP_VARIANT => Self::MapVariant(Box::new((
+            expression_discriminant::SET_UNION => Self::SetUnion(Box::new((
                 Box::pin(Self::read(read)).await?,
-                read.read_u32().await?,
                 Box::pin(Self::read(read)).await?,
             ))),
-            expression_discriminant::FUSE => {
-                Self::Fuse(Box::new(Box::pin(Self::read(read)).await?))
+            expression_discriminant::SET_INTERSECTION => Self::SetIntersection(Box::new((
+                Box::pin(Self::read(read)).await?,
+                Box::pin(Self::read(read)).await?,
+            ))),
+            expression_discriminant::SET_DIFFERENCE => Self::SetDifference(Box::new((
+                Box::pin(Self::read(read)).await?,
+                Box::pin(Self::read(read)).await?,
+            ))),
+            expression_discriminant::CONCAT => Self::Concat(Box::new((
+                Box::pin(Self::read(read)).await?,
+                Box::pin(Self::read(read)).await?,
+            ))),
+            expression_discriminant::STRING_CONTAINS => Self::StringContains(Box::new((
+                Box::pin(Self::read(read)).await?,
+                Box::pin(Self::read(read)).await?,
+            ))),
+            expression_discriminant::STARTS_WITH => Self::StartsWith(Box::new((
+                Box::pin(Self::read(read)).await?,
+                Box::pin(Self::read(read)).await?,
+            ))),
+            expression_discriminant::ENDS_WITH => Self::EndsWith(Box::new((
+                Box::pin(Self::read(read)).await?,
+                Box::pin(Self::read(read)).await?,
+            ))),
+            expression_discriminant::STRING_LENGTH => {
+                Self::StringLength(Box::new(Box::pin(Self::read(read)).await?))
             }
-            expression_discriminant::CHAIN => Self::Chain(Box::new((
+            expression_discriminant::SUBSTRING => Self::Substring(Box::new((
+                Box::pin(Self::read(read)).await?,
                 Box::pin(Self::read(read)).await?,
                 Box::pin(Self::read(read)).await?,
             ))),
-            expression_discriminant::GET => Self::Get(Box::new((
+            expression_discriminant::TO_LOWERCASE => {
+                Self::ToLowercase(Box::new(Box::pin(Self::read(read)).await?))
+            }
+            expression_discriminant::TO_UPPERCASE => {
+                Self::ToUppercase(Box::new(Box::pin(Self::read(read)).await?))
+            }
+            // The pattern is compiled here so it isn't for each string matched.
+            expression_discriminant::MATCHES => Self::Matches(Box::new((
                 Box::pin(Self::read(read)).await?,
+                Pattern::read(read).await?.compile(),
+            ))),
+            expression_discriminant::LIST_SUM => Self::ListSum(Box::new((
                 Box::pin(Self::read(read)).await?,
+                SchemaNode::read(read).await?,
             ))),
-            expression_discriminant::CONDITION => Self::Condition(Box::new((
+            expression_discriminant::MIN => Self::Min(Box::new(Box::pin(Self::read(read)).await?)),
+            expression_discriminant::MAX => Self::Max(Box::new(Box::pin(Self::read(read)).await?)),
+            expression_discriminant::AVERAGE => {
+                Self::Average(Box::new(Box::pin(Self::read(read)).await?))
+            }
+            expression_discriminant::MIN_BY => Self::MinBy(Box::new((
+                Box::pin(Self::read(read)).await?,
                 Box::pin(Self::read(read)).await?,
+            ))),
+            expression_discriminant::MAX_BY => Self::MaxBy(Box::new((
                 Box::pin(Self::read(read)).await?,
                 Box::pin(Self::read(read)).await?,
             ))),
-            expression_discriminant::PRODUCT => {
-                let length: usize = read.read_u32().await?.try_into().map_err(|_| {
-                    io_error!(
-                        OutOfMemory,
-                        "product expression length doesn't fit into a pointer sized unsigned integer",
-                    )
-                })?;
-
-                let mut fields = Vec::new();
-                fields.try_reserve(length).map_err(|_| {
-                    io_error!(
-                        OutOfMemory,
-                        "allocation of memory for product expression failed"
-                    )
-                })?;
-
-                for _ in 0..length {
-                    fields.push(Box::pin(Self::read(read)).await?);
-                }
-
-                Self::Product(fields)
+            expression_discriminant::COUNT => Self::Count(Box::new((
+                Box::pin(Self::read(read)).await?,
+                Box::pin(Self::read(read)).await?,
+            ))),
+            expression_discriminant::SORTED => {
+                let (list, keys) = Self::read_sort(read).await?;
+                Self::Sorted(Box::new((list, keys)))
             }
-            expression_discriminant::SUM => Self::Sum(Box::new((
-                read.read_u32().await?,
+            expression_discriminant::SORT => {
+                let (list, keys) = Self::read_sort(read).await?;
+                Self::Sort(Box::new((list, keys)))
+            }
+            expression_discriminant::REVERSED => {
+                Self::Reversed(Box::new(Box::pin(Self::read(read)).await?))
+            }
+            expression_discriminant::REVERSE => {
+                Self::Reverse(Box::new(Box::pin(Self::read(read)).await?))
+            }
+            expression_discriminant::SKIP => Self::Skip(Box::new((
+                Box::pin(Self::read(read)).await?,
                 Box::pin(Self::read(read)).await?,
             ))),
-            expression_discriminant::LIST => {
+            expression_discriminant::TAKE => Self::Take(Box::new((
+                Box::pin(Self::read(read)).await?,
+                Box::pin(Self::read(read)).await?,
+            ))),
+            expression_discriminant::GROUP_BY => Self::GroupBy(Box::new((
+                Box::pin(Self::read(read)).await?,
+                Box::pin(Self::read(read)).await?,
+            ))),
+            expression_discriminant::TRANSFORM_VALUES => Self::TransformValues(Box::new((
+                Box::pin(Self::read(read)).await?,
+                Box::pin(Self::read(read)).await?,
+            ))),
+            expression_discriminant::SLOT_MAP_PAGE => Self::SlotMapPage(Box::new((
+                Box::pin(Self::read(read)).await?,
+                Box::pin(Self::read(read)).await?,
+                Box::pin(Self::read(read)).await?,
+            ))),
+            expression_discriminant::LET => Self::Let(Box::new((
+                Box::pin(Self::read(read)).await?,
+                Box::pin(Self::read(read)).await?,
+            ))),
+            expression_discriminant::IS_VARIANT => Self::IsVariant(Box::new((
+                Box::pin(Self::read(read)).await?,
+                read.read_u32().await?,
+            ))),
+            expression_discriminant::MATCH => {
+                let sum = Box::pin(Self::read(read)).await?;
+
                 let length: usize = read.read_u32().await?.try_into().map_err(|_| {
                     io_error!(
                         OutOfMemory,
-                        "list expression length doesn't fit into a pointer sized unsigned integer",
+                        "match arm count doesn't fit into a pointer sized unsigned integer",
                     )
                 })?;
 
-                let mut elements = Vec::new();
-                elements.try_reserve(length).map_err(|_| {
-                    io_error!(
-                        OutOfMemory,
-                        "allocation of memory for list expression failed"
-                    )
+                let mut arms = Vec::new();
+                arms.try_reserve(length).map_err(|_| {
+                    io_error!(OutOfMemory, "allocation of memory for match arms failed")
                 })?;
 
                 for _ in 0..length {
-                    elements.push(Box::pin(Self::read(read)).await?);
+                    arms.push(Box::pin(Self::read(read)).await?);
                 }
 
-                Self::List(elements)
+                Self::Match(Box::new((sum, arms)))
             }
+            expression_discriminant::JOIN => Self::Join(Box::new((
+                Box::pin(Self::read(read)).await?,
+                Box::pin(Self::read(read)).await?,
+                Box::pin(Self::read(read)).await?,
+            ))),
             _ => {
                 return Err(io_error!(
                     InvalidData,
@@ -460,7 +2197,7 @@ impl ExpressionNode {
                 Box::pin(operands.as_ref().0.write(write)).await?;
                 Box::pin(operands.as_ref().1.write(write)).await?;
             }
-            ExpressionNode::Map(operands) => {
+            ExpressionNode::Map(operands) | ExpressionNode::Let(operands) => {
                 Box::pin(operands.as_ref().0.write(write)).await?;
                 Box::pin(operands.as_ref().1.write(write)).await?;
             }
@@ -481,9 +2218,78 @@ impl ExpressionNode {
                 write.write_u32(operands.as_ref().1).await?;
                 Box::pin(operands.as_ref().2.write(write)).await?;
             }
-            ExpressionNode::Fuse(operand) => {
+            ExpressionNode::Fuse(operand)
+            | ExpressionNode::Not(operand)
+            | ExpressionNode::Pop(operand)
+            | ExpressionNode::Clear(operand)
+            | ExpressionNode::MapKeys(operand)
+            | ExpressionNode::MapValues(operand)
+            | ExpressionNode::MapEntries(operand)
+            | ExpressionNode::StringLength(operand)
+            | ExpressionNode::ToLowercase(operand)
+            | ExpressionNode::ToUppercase(operand)
+            | ExpressionNode::Min(operand)
+            | ExpressionNode::Max(operand)
+            | ExpressionNode::Average(operand)
+            | ExpressionNode::Reversed(operand)
+            | ExpressionNode::Reverse(operand) => {
                 Box::pin(operand.write(write)).await?;
             }
+            ExpressionNode::IsVariant(operands) => {
+                Box::pin(operands.as_ref().0.write(write)).await?;
+                write.write_u32(operands.as_ref().1).await?;
+            }
+            ExpressionNode::Match(operands) => {
+                let (sum, arms) = operands.as_ref();
+
+                Box::pin(sum.write(write)).await?;
+                write
+                    .write_u32(arms.len().try_into().map_err(|_| {
+                        io_error!(
+                            OutOfMemory,
+                            "match arm count doesn't fit into a 32 bit unsigned integer",
+                        )
+                    })?)
+                    .await?;
+
+                for arm in arms {
+                    Box::pin(arm.write(write)).await?;
+                }
+            }
+            ExpressionNode::Sorted(operands) | ExpressionNode::Sort(operands) => {
+                let (list, keys) = operands.as_ref();
+
+                Box::pin(list.write(write)).await?;
+                write
+                    .write_u32(keys.len().try_into().map_err(|_| {
+                        io_error!(
+                            OutOfMemory,
+                            "sort key count doesn't fit into a 32 bit unsigned integer",
+                        )
+                    })?)
+                    .await?;
+
+                for (key, descending) in keys {
+                    write.write_u8(*descending as u8).await?;
+                    Box::pin(key.write(write)).await?;
+                }
+            }
+            ExpressionNode::ListSum(operands) => {
+                Box::pin(operands.as_ref().0.write(write)).await?;
+                Box::pin(operands.as_ref().1.write(write)).await?;
+            }
+            ExpressionNode::Matches(operands) => {
+                Box::pin(operands.as_ref().0.write(write)).await?;
+                operands.as_ref().1.pattern().write(write).await?;
+            }
+            ExpressionNode::MapInsert(operands)
+            | ExpressionNode::Substring(operands)
+            | ExpressionNode::SlotMapPage(operands)
+            | ExpressionNode::Join(operands) => {
+                Box::pin(operands.as_ref().0.write(write)).await?;
+                Box::pin(operands.as_ref().1.write(write)).await?;
+                Box::pin(operands.as_ref().2.write(write)).await?;
+            }
             ExpressionNode::Chain(operands) => {
                 Box::pin(operands.as_ref().0.write(write)).await?;
                 Box::pin(operands.as_ref().1.write(write)).await?;
@@ -529,8 +2335,451 @@ impl ExpressionNode {
                     Box::pin(element.write(write)).await?;
                 }
             }
+            ExpressionNode::Add(operands)
+            | ExpressionNode::Sub(operands)
+            | ExpressionNode::Mul(operands)
+            | ExpressionNode::Div(operands)
+            | ExpressionNode::Rem(operands)
+            | ExpressionNode::Less(operands)
+            | ExpressionNode::LessEqual(operands)
+            | ExpressionNode::Greater(operands)
+            | ExpressionNode::GreaterEqual(operands)
+            | ExpressionNode::Or(operands)
+            | ExpressionNode::Xor(operands)
+            | ExpressionNode::NotEqual(operands)
+            | ExpressionNode::Push(operands)
+            | ExpressionNode::Remove(operands)
+            | ExpressionNode::Truncate(operands)
+            | ExpressionNode::Extend(operands)
+            | ExpressionNode::SlotMapInsert(operands)
+            | ExpressionNode::SlotMapRemove(operands)
+            | ExpressionNode::MapGet(operands)
+            | ExpressionNode::MapRemove(operands)
+            | ExpressionNode::MapContainsKey(operands)
+            | ExpressionNode::SetContains(operands)
+            | ExpressionNode::SetInsert(operands)
+            | ExpressionNode::SetRemove(operands)
+            | ExpressionNode::SetUnion(operands)
+            | ExpressionNode::SetIntersection(operands)
+            | ExpressionNode::SetDifference(operands)
+            | ExpressionNode::Concat(operands)
+            | ExpressionNode::StringContains(operands)
+            | ExpressionNode::StartsWith(operands)
+            | ExpressionNode::EndsWith(operands)
+            | ExpressionNode::MinBy(operands)
+            | ExpressionNode::MaxBy(operands)
+            | ExpressionNode::Count(operands)
+            | ExpressionNode::Skip(operands)
+            | ExpressionNode::Take(operands)
+            | ExpressionNode::GroupBy(operands)
+            | ExpressionNode::TransformValues(operands) => {
+                Box::pin(operands.as_ref().0.write(write)).await?;
+                Box::pin(operands.as_ref().1.write(write)).await?;
+            }
         }
 
         Ok(())
     }
 }
+
+/// Fields of a product of two values.
+///
+/// The value of a [`SlotMap`](crate::SlotMap) is a product of its slots and of
+/// the indices of its free slots, each slot being a product of its generation
+/// and an optional value.
+fn pair(value: &Value, discriminant: u8) -> Result<&[Arc<Mutex<Value>>; 2], QueryError> {
+    match value {
+        Value::Product(fields) => fields
+            .as_slice()
+            .try_into()
+            .map_err(|_| QueryError::InvalidOperand(discriminant)),
+        _ => Err(QueryError::InvalidOperand(discriminant)),
+    }
+}
+
+/// Insert `value` into a slot map, returning the index and generation of its
+/// slot, same as [`SlotMap::insert`](crate::SlotMap::insert).
+fn slot_map_insert(slot_map: &Value, value: Value) -> Result<(u32, u32), QueryError> {
+    let discriminant = expression_discriminant::SLOT_MAP_INSERT;
+    let invalid_operand = || QueryError::InvalidOperand(discriminant);
+
+    let [slots, free] = pair(slot_map, discriminant)?;
+
+    let mut slots = slots.lock().unwrap();
+    let Value::List(slots) = &mut *slots else {
+        return Err(invalid_operand());
+    };
+    let mut free = free.lock().unwrap();
+    let Value::List(free) = &mut *free else {
+        return Err(invalid_operand());
+    };
+
+    let Some(index) = free.last() else {
+        let index = slots.len().try_into().unwrap();
+
+        slots.push(Arc::new(Mutex::new(Value::Product(vec![
+            Arc::new(Mutex::new(Value::Uint32(1))),
+            Arc::new(Mutex::new(Value::Sum(1, Arc::new(Mutex::new(value))))),
+        ]))));
+
+        return Ok((index, 1));
+    };
+
+    let Value::Uint32(index) = *index.lock().unwrap() else {
+        return Err(invalid_operand());
+    };
+
+    let slot = slots
+        .get(index as usize)
+        .ok_or_else(invalid_operand)?
+        .lock()
+        .unwrap();
+    let [generation, option] = pair(&slot, discriminant)?;
+
+    let Value::Uint32(generation) = *generation.lock().unwrap() else {
+        return Err(invalid_operand());
+    };
+
+    // The free index is only taken once its slot is known to be valid, so a
+    // failed insert leaves the free list untouched.
+    free.pop();
+    *option.lock().unwrap() = Value::Sum(1, Arc::new(Mutex::new(value)));
+
+    Ok((index, generation))
+}
+
+/// Remove the value of the key with the given index and generation from a slot
+/// map, returning it as an optional value, same as
+/// [`SlotMap::remove`](crate::SlotMap::remove).
+fn slot_map_remove(slot_map: &Value, index: u32, generation: u32) -> Result<Value, QueryError> {
+    let discriminant = expression_discriminant::SLOT_MAP_REMOVE;
+    let invalid_operand = || QueryError::InvalidOperand(discriminant);
+    let none = || Value::Sum(0, Arc::new(Mutex::new(Value::Unit)));
+
+    let [slots, free] = pair(slot_map, discriminant)?;
+
+    let slots = slots.lock().unwrap();
+    let Value::List(slots) = &*slots else {
+        return Err(invalid_operand());
+    };
+
+    let Some(slot) = slots.get(index as usize) else {
+        return Ok(none());
+    };
+    let slot = slot.lock().unwrap();
+    let [slot_generation, option] = pair(&slot, discriminant)?;
+
+    let mut slot_generation = slot_generation.lock().unwrap();
+    let Value::Uint32(slot_generation) = &mut *slot_generation else {
+        return Err(invalid_operand());
+    };
+
+    if *slot_generation != generation {
+        return Ok(none());
+    }
+
+    let mut option = option.lock().unwrap();
+    match &*option {
+        Value::Sum(0, _) => return Ok(none()),
+        Value::Sum(1, _) => {}
+        _ => return Err(invalid_operand()),
+    }
+
+    // A slot whose generation can't be bumped is never reused, so no key of
+    // a previous value can resolve to a new one.
+    if let Some(next_generation) = slot_generation.checked_add(1) {
+        let mut free = free.lock().unwrap();
+        let Value::List(free) = &mut *free else {
+            return Err(invalid_operand());
+        };
+
+        *slot_generation = next_generation;
+        free.push(Arc::new(Mutex::new(Value::Uint32(index))));
+    }
+
+    Ok(mem::replace(&mut *option, none()))
+}
+
+/// Value of the slot `index` of a slot map as an optional value, which is
+/// `None` if the slot doesn't exist or its generation isn't `generation`.
+fn slot_map_get(
+    slots: &[Arc<Mutex<Value>>],
+    index: u32,
+    generation: u32,
+) -> Result<Value, QueryError> {
+    let discriminant = expression_discriminant::JOIN;
+    let none = || Value::Sum(0, Arc::new(Mutex::new(Value::Unit)));
+
+    let Some(slot) = slots.get(index as usize) else {
+        return Ok(none());
+    };
+    let slot = slot.lock().unwrap();
+    let [slot_generation, option] = pair(&slot, discriminant)?;
+
+    let Value::Uint32(slot_generation) = *slot_generation.lock().unwrap() else {
+        return Err(QueryError::InvalidOperand(discriminant));
+    };
+
+    if slot_generation != generation {
+        return Ok(none());
+    }
+
+    let option = option.lock().unwrap();
+    match &*option {
+        Value::Sum(0 | 1, _) => Ok(option.clone()),
+        _ => Err(QueryError::InvalidOperand(discriminant)),
+    }
+}
+
+/// Entries of a slot map whose key is after `cursor`, at most `limit` of them.
+///
+/// Keys are ordered by index then generation. The generation of a slot only
+/// grows, so the entries inserted or removed after a cursor was returned
+/// don't move the entries after it to before it or the other way around.
+fn slot_map_page(
+    slot_map: &Value,
+    cursor: Option<(u32, u32)>,
+    limit: u32,
+) -> Result<Vec<Arc<Mutex<Value>>>, QueryError> {
+    let discriminant = expression_discriminant::SLOT_MAP_PAGE;
+    let invalid_operand = || QueryError::InvalidOperand(discriminant);
+
+    let [slots, _] = pair(slot_map, discriminant)?;
+
+    let slots = slots.lock().unwrap();
+    let Value::List(slots) = &*slots else {
+        return Err(invalid_operand());
+    };
+
+    // Slots before the one of the cursor can't hold a key after it.
+    let start = cursor.map_or(0, |(index, _)| index as usize);
+
+    let mut entries = Vec::new();
+    for (index, slot) in slots.iter().enumerate().skip(start) {
+        if entries.len() >= limit as usize {
+            break;
+        }
+
+        let index = u32::try_from(index).map_err(|_| invalid_operand())?;
+
+        let slot = slot.lock().unwrap();
+        let [generation, option] = pair(&slot, discriminant)?;
+
+        let Value::Uint32(generation) = *generation.lock().unwrap() else {
+            return Err(invalid_operand());
+        };
+
+        if cursor.is_some_and(|cursor| (index, generation) <= cursor) {
+            continue;
+        }
+
+        let option = option.lock().unwrap();
+        match &*option {
+            Value::Sum(0, _) => {}
+            Value::Sum(1, value) => entries.push(Arc::new(Mutex::new(Value::Product(vec![
+                Arc::new(Mutex::new(Value::Product(vec![
+                    Arc::new(Mutex::new(Value::Uint32(index))),
+                    Arc::new(Mutex::new(Value::Uint32(generation))),
+                ]))),
+                value.clone(),
+            ])))),
+            _ => return Err(invalid_operand()),
+        }
+    }
+
+    Ok(entries)
+}
+
+/// Index and generation of a slot map key.
+fn key_fields(key: &Value, discriminant: u8) -> Result<(u32, u32), QueryError> {
+    let [index, generation] = pair(key, discriminant)?;
+
+    match (&*index.lock().unwrap(), &*generation.lock().unwrap()) {
+        (Value::Uint32(index), Value::Uint32(generation)) => Ok((*index, *generation)),
+        _ => Err(QueryError::InvalidOperand(discriminant)),
+    }
+}
+
+/// Zero of a numeric schema.
+fn zero(schema: &SchemaNode) -> Option<Value> {
+    Some(match schema {
+        SchemaNode::Uint8 => Value::Uint8(0),
+        SchemaNode::Uint16 => Value::Uint16(0),
+        SchemaNode::Uint32 => Value::Uint32(0),
+        SchemaNode::Uint64 => Value::Uint64(0),
+        SchemaNode::Uint128 => Value::Uint128(0),
+        SchemaNode::Int8 => Value::Int8(0),
+        SchemaNode::Int16 => Value::Int16(0),
+        SchemaNode::Int32 => Value::Int32(0),
+        SchemaNode::Int64 => Value::Int64(0),
+        SchemaNode::Int128 => Value::Int128(0),
+        SchemaNode::Float32 => Value::Float32(0.0),
+        SchemaNode::Float64 => Value::Float64(0.0),
+        _ => return None,
+    })
+}
+
+/// Numeric value as a float, rounded to the nearest one if needed.
+fn to_f64(value: &Value) -> Option<f64> {
+    Some(match *value {
+        Value::Uint8(value) => value.into(),
+        Value::Uint16(value) => value.into(),
+        Value::Uint32(value) => value.into(),
+        Value::Uint64(value) => value as f64,
+        Value::Uint128(value) => value as f64,
+        Value::Int8(value) => value.into(),
+        Value::Int16(value) => value.into(),
+        Value::Int32(value) => value.into(),
+        Value::Int64(value) => value as f64,
+        Value::Int128(value) => value as f64,
+        Value::Float32(value) => value.into(),
+        Value::Float64(value) => value,
+        _ => return None,
+    })
+}
+
+/// Apply the arithmetic expression with the given discriminant to two values.
+///
+/// Integer operations are checked, floats follow IEEE 754 so they never fail.
+fn arithmetic(discriminant: u8, lhs: &Value, rhs: &Value) -> Result<Value, QueryError> {
+    macro_rules! integers {
+        ($($variant:ident)*) => {
+            match (lhs, rhs) {
+                $(
+                    (Value::$variant(lhs), Value::$variant(rhs)) => {
+                        let result = match discriminant {
+                            expression_discriminant::ADD => lhs.checked_add(*rhs),
+                            expression_discriminant::SUB => lhs.checked_sub(*rhs),
+                            expression_discriminant::MUL => lhs.checked_mul(*rhs),
+                            expression_discriminant::DIV | expression_discriminant::REM
+                                if *rhs == 0 =>
+                            {
+                                return Err(QueryError::DivisionByZero)
+                            }
+                            // Only fails for the minimum of a signed type divided by -1.
+                            expression_discriminant::DIV => lhs.checked_div(*rhs),
+                            expression_discriminant::REM => lhs.checked_rem(*rhs),
+                            _ => unreachable!("not an arithmetic expression"),
+                        };
+
+                        return result
+                            .map(Value::$variant)
+                            .ok_or(QueryError::Overflow(discriminant));
+                    }
+                )*
+                _ => {}
+            }
+        };
+    }
+
+    macro_rules! floats {
+        ($($variant:ident)*) => {
+            match (lhs, rhs) {
+                $(
+                    (Value::$variant(lhs), Value::$variant(rhs)) => {
+                        return Ok(Value::$variant(match discriminant {
+                            expression_discriminant::ADD => lhs + rhs,
+                            expression_discriminant::SUB => lhs - rhs,
+                            expression_discriminant::MUL => lhs * rhs,
+                            expression_discriminant::DIV => lhs / rhs,
+                            expression_discriminant::REM => lhs % rhs,
+                            _ => unreachable!("not an arithmetic expression"),
+                        }));
+                    }
+                )*
+                _ => {}
+            }
+        };
+    }
+
+    integers!(Uint8 Uint16 Uint32 Uint64 Uint128 Int8 Int16 Int32 Int64 Int128);
+    floats!(Float32 Float64);
+
+    Err(QueryError::InvalidOperand(discriminant))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn integer_overflow_is_an_error() {
+        let overflow = |discriminant, lhs, rhs| {
+            matches!(
+                arithmetic(discriminant, &lhs, &rhs),
+                Err(QueryError::Overflow(overflow)) if overflow == discriminant
+            )
+        };
+
+        assert!(overflow(
+            expression_discriminant::ADD,
+            Value::Uint32(u32::MAX),
+            Value::Uint32(1),
+        ));
+        assert!(overflow(
+            expression_discriminant::SUB,
+            Value::Uint8(0),
+            Value::Uint8(1),
+        ));
+        assert!(overflow(
+            expression_discriminant::MUL,
+            Value::Int64(i64::MAX),
+            Value::Int64(2),
+        ));
+        assert!(overflow(
+            expression_discriminant::DIV,
+            Value::Int32(i32::MIN),
+            Value::Int32(-1),
+        ));
+        assert!(overflow(
+            expression_discriminant::REM,
+            Value::Int128(i128::MIN),
+            Value::Int128(-1),
+        ));
+
+        assert!(matches!(
+            arithmetic(
+                expression_discriminant::ADD,
+                &Value::Uint32(u32::MAX - 1),
+                &Value::Uint32(1),
+            ),
+            Ok(Value::Uint32(u32::MAX)),
+        ));
+    }
+
+    #[test]
+    fn integer_division_by_zero_is_an_error() {
+        for discriminant in [expression_discriminant::DIV, expression_discriminant::REM] {
+            assert!(matches!(
+                arithmetic(discriminant, &Value::Uint16(1), &Value::Uint16(0)),
+                Err(QueryError::DivisionByZero),
+            ));
+            assert!(matches!(
+                arithmetic(discriminant, &Value::Int8(1), &Value::Int8(0)),
+                Err(QueryError::DivisionByZero),
+            ));
+        }
+
+        // Floats follow IEEE 754 instead.
+        assert!(matches!(
+            arithmetic(
+                expression_discriminant::DIV,
+                &Value::Float64(1.0),
+                &Value::Float64(0.0),
+            ),
+            Ok(Value::Float64(f64::INFINITY)),
+        ));
+    }
+
+    #[test]
+    fn failed_arithmetic_fails_the_query() {
+        let uint32 = |value| ExpressionNode::Value(SchemaNode::Uint32, Value::Uint32(value));
+
+        let division = ExpressionNode::Div(Box::new((uint32(1), uint32(0))));
+
+        assert!(matches!(
+            division.evaluate(vec![Arc::new(Mutex::new(Value::Unit))]),
+            Err(QueryError::DivisionByZero),
+        ));
+    }
+}
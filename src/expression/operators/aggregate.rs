@@ -0,0 +1,114 @@
+use std::marker::PhantomData;
+
+use super::element_expression;
+use crate::{
+    AverageExpression, CountExpression, Expression, ListSumExpression, MaxByExpression,
+    MaxExpression, MinByExpression, MinExpression, Numeric, Ordered, Schema,
+};
+
+/// Aggregations of the elements of a list.
+///
+/// Unlike [`Compare`](crate::Compare), `min`, `max`, `min_by` and `max_by`
+/// order floats by their `total_cmp` method, so a NaN is greater than any
+/// other float. When several elements are the least or the greatest, the first
+/// one is returned.
+pub trait Aggregate<T: Schema + Send + Sync>: Expression<Target = Vec<T>> + Sized {
+    /// Sum of the elements, zero for an empty list. Integer sums fail with
+    /// [`QueryError::Overflow`](crate::QueryError::Overflow) when they don't
+    /// fit into their type.
+    fn sum(self) -> ListSumExpression<Self, T::Output>
+    where
+        T: Numeric;
+
+    fn min(self) -> MinExpression<Self, T>
+    where
+        T: Ordered;
+
+    fn max(self) -> MaxExpression<Self, T>
+    where
+        T: Ordered;
+
+    /// Mean of the elements, `None` for an empty list.
+    fn average(self) -> AverageExpression<Self>
+    where
+        T: Numeric;
+
+    /// Element with the least key.
+    fn min_by<K: Expression>(
+        self,
+        key: impl FnOnce(T::Expression) -> K,
+    ) -> MinByExpression<Self, K, T>
+    where
+        K::Target: Ordered;
+
+    /// Element with the greatest key.
+    fn max_by<K: Expression>(
+        self,
+        key: impl FnOnce(T::Expression) -> K,
+    ) -> MaxByExpression<Self, K, T>
+    where
+        K::Target: Ordered;
+
+    /// Number of elements for which `predicate` is true.
+    fn count<R: Expression<Target = bool>>(
+        self,
+        predicate: impl FnOnce(T::Expression) -> R,
+    ) -> CountExpression<Self, R>;
+}
+
+impl<T: Schema + Send + Sync, E: Expression<Target = Vec<T>>> Aggregate<T> for E {
+    fn sum(self) -> ListSumExpression<Self, T::Output>
+    where
+        T: Numeric,
+    {
+        ListSumExpression(self, PhantomData)
+    }
+
+    fn min(self) -> MinExpression<Self, T>
+    where
+        T: Ordered,
+    {
+        MinExpression(self, PhantomData)
+    }
+
+    fn max(self) -> MaxExpression<Self, T>
+    where
+        T: Ordered,
+    {
+        MaxExpression(self, PhantomData)
+    }
+
+    fn average(self) -> AverageExpression<Self>
+    where
+        T: Numeric,
+    {
+        AverageExpression(self)
+    }
+
+    fn min_by<K: Expression>(
+        self,
+        key: impl FnOnce(T::Expression) -> K,
+    ) -> MinByExpression<Self, K, T>
+    where
+        K::Target: Ordered,
+    {
+        MinByExpression(self, element_expression::<T, _>(key), PhantomData)
+    }
+
+    fn max_by<K: Expression>(
+        self,
+        key: impl FnOnce(T::Expression) -> K,
+    ) -> MaxByExpression<Self, K, T>
+    where
+        K::Target: Ordered,
+    {
+        MaxByExpression(self, element_expression::<T, _>(key), PhantomData)
+    }
+
+    fn count<R: Expression<Target = bool>>(
+        self,
+        predicate: impl FnOnce(T::Expression) -> R,
+    ) -> CountExpression<Self, R> {
+        CountExpression(self, element_expression::<T, _>(predicate))
+    }
+}
@@ -0,0 +1,85 @@
+use std::num::{
+    NonZeroI128, NonZeroI16, NonZeroI32, NonZeroI64, NonZeroI8, NonZeroU128, NonZeroU16,
+    NonZeroU32, NonZeroU64, NonZeroU8,
+};
+
+use crate::{
+    AddExpression, DivExpression, Expression, MulExpression, RemExpression, Schema, SubExpression,
+};
+
+/// Types on which [`Arithmetic`] operators are available.
+///
+/// Integer operations fail with [`QueryError::Overflow`](crate::QueryError::Overflow)
+/// when the result doesn't fit into the type, and division or remainder by zero
+/// fail with [`QueryError::DivisionByZero`](crate::QueryError::DivisionByZero).
+/// Float operations follow IEEE 754, they never fail.
+pub trait Numeric: Schema + Send + Sync {
+    /// Type of the result of an operation, the non-zero integers result in
+    /// their plain counterpart because a subtraction or a remainder can be zero.
+    type Output: Schema + Send + Sync;
+}
+
+macro_rules! impl_numeric {
+    ($($target:ty => $output:ty;)*) => {
+        $(
+            impl Numeric for $target {
+                type Output = $output;
+            }
+        )*
+    };
+}
+
+impl_numeric!(
+    u8 => u8;
+    u16 => u16;
+    u32 => u32;
+    u64 => u64;
+    u128 => u128;
+    i8 => i8;
+    i16 => i16;
+    i32 => i32;
+    i64 => i64;
+    i128 => i128;
+    f32 => f32;
+    f64 => f64;
+    NonZeroU8 => u8;
+    NonZeroU16 => u16;
+    NonZeroU32 => u32;
+    NonZeroU64 => u64;
+    NonZeroU128 => u128;
+    NonZeroI8 => i8;
+    NonZeroI16 => i16;
+    NonZeroI32 => i32;
+    NonZeroI64 => i64;
+    NonZeroI128 => i128;
+);
+
+pub trait Arithmetic<Rhs: Expression>: Expression + Sized {
+    fn add(self, rhs: Rhs) -> AddExpression<Self, Rhs>;
+    fn sub(self, rhs: Rhs) -> SubExpression<Self, Rhs>;
+    fn mul(self, rhs: Rhs) -> MulExpression<Self, Rhs>;
+    fn div(self, rhs: Rhs) -> DivExpression<Self, Rhs>;
+    fn rem(self, rhs: Rhs) -> RemExpression<Self, Rhs>;
+}
+
+impl<T: Numeric, L: Expression<Target = T>, R: Expression<Target = T>> Arithmetic<R> for L {
+    fn add(self, rhs: R) -> AddExpression<Self, R> {
+        AddExpression(self, rhs)
+    }
+
+    fn sub(self, rhs: R) -> SubExpression<Self, R> {
+        SubExpression(self, rhs)
+    }
+
+    fn mul(self, rhs: R) -> MulExpression<Self, R> {
+        MulExpression(self, rhs)
+    }
+
+    fn div(self, rhs: R) -> DivExpression<Self, R> {
+        DivExpression(self, rhs)
+    }
+
+    fn rem(self, rhs: R) -> RemExpression<Self, R> {
+        RemExpression(self, rhs)
+    }
+}
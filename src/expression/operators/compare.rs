@@ -0,0 +1,78 @@
+use std::{
+    num::{
+        NonZeroI128, NonZeroI16, NonZeroI32, NonZeroI64, NonZeroI8, NonZeroU128, NonZeroU16,
+        NonZeroU32, NonZeroU64, NonZeroU8,
+    },
+    time::Duration,
+};
+
+use crate::{
+    Expression, GreaterEqualExpression, GreaterExpression, LessEqualExpression, LessExpression,
+    Schema,
+};
+
+/// Types on which [`Compare`] operators are available.
+///
+/// Strings are ordered lexicographically by bytes. Comparisons involving a NaN
+/// float are always false.
+pub trait Ordered: Schema + Send + Sync {}
+
+macro_rules! impl_ordered {
+    ($($target:ty),* $(,)?) => {
+        $(
+            impl Ordered for $target {}
+        )*
+    };
+}
+
+impl_ordered!(
+    String,
+    Duration,
+    u8,
+    u16,
+    u32,
+    u64,
+    u128,
+    i8,
+    i16,
+    i32,
+    i64,
+    i128,
+    f32,
+    f64,
+    NonZeroU8,
+    NonZeroU16,
+    NonZeroU32,
+    NonZeroU64,
+    NonZeroU128,
+    NonZeroI8,
+    NonZeroI16,
+    NonZeroI32,
+    NonZeroI64,
+    NonZeroI128,
+);
+
+pub trait Compare<Rhs: Expression>: Expression + Sized {
+    fn less(self, rhs: Rhs) -> LessExpression<Self, Rhs>;
+    fn less_equal(self, rhs: Rhs) -> LessEqualExpression<Self, Rhs>;
+    fn greater(self, rhs: Rhs) -> GreaterExpression<Self, Rhs>;
+    fn greater_equal(self, rhs: Rhs) -> GreaterEqualExpression<Self, Rhs>;
+}
+
+impl<T: Ordered, L: Expression<Target = T>, R: Expression<Target = T>> Compare<R> for L {
+    fn less(self, rhs: R) -> LessExpression<Self, R> {
+        LessExpression(self, rhs)
+    }
+
+    fn less_equal(self, rhs: R) -> LessEqualExpression<Self, R> {
+        LessEqualExpression(self, rhs)
+    }
+
+    fn greater(self, rhs: R) -> GreaterExpression<Self, R> {
+        GreaterExpression(self, rhs)
+    }
+
+    fn greater_equal(self, rhs: R) -> GreaterEqualExpression<Self, R> {
+        GreaterEqualExpression(self, rhs)
+    }
+}
@@ -1,6 +1,6 @@
 use std::marker::PhantomData;
 
-use crate::{ConditionExpression, Expression, Schema};
+use crate::{ConditionExpression, Expression, NotExpression, Schema};
 
 pub trait BoolOperators: Expression<Target = bool> + Sized {
     fn if_else<T: Schema, I: Expression<Target = T>, E: Expression<Target = T>>(
@@ -8,6 +8,8 @@ pub trait BoolOperators: Expression<Target = bool> + Sized {
         if_branch: I,
         else_branch: E,
     ) -> ConditionExpression<Self, T, I, E>;
+
+    fn not(self) -> NotExpression<Self>;
 }
 
 impl<B: Expression<Target = bool>> BoolOperators for B {
@@ -18,4 +20,8 @@ impl<B: Expression<Target = bool>> BoolOperators for B {
     ) -> ConditionExpression<Self, T, I, E> {
         ConditionExpression(self, if_branch, else_branch, PhantomData)
     }
+
+    fn not(self) -> NotExpression<Self> {
+        NotExpression(self)
+    }
 }
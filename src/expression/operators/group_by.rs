@@ -0,0 +1,30 @@
+use std::{collections::HashMap, hash::Hash, marker::PhantomData};
+
+use super::element_expression;
+use crate::{Expression, GroupByExpression, Schema};
+
+/// Grouping of the elements of a list.
+pub trait GroupBy<T: Schema + Send + Sync>: Expression<Target = Vec<T>> + Sized {
+    /// Map of each key to the elements it is the key of, in the order of the
+    /// list. Groups are in the order of the first element of each, and their
+    /// values can be aggregated on the server with
+    /// [`HashMapOperators::map_values`](crate::HashMapOperators::map_values).
+    fn group_by<K: Expression>(
+        self,
+        key: impl FnOnce(T::Expression) -> K,
+    ) -> GroupByExpression<Self, K, HashMap<K::Target, Vec<T>>>
+    where
+        K::Target: Send + Sync + Eq + Hash;
+}
+
+impl<T: Schema + Send + Sync, E: Expression<Target = Vec<T>>> GroupBy<T> for E {
+    fn group_by<K: Expression>(
+        self,
+        key: impl FnOnce(T::Expression) -> K,
+    ) -> GroupByExpression<Self, K, HashMap<K::Target, Vec<T>>>
+    where
+        K::Target: Send + Sync + Eq + Hash,
+    {
+        GroupByExpression(self, element_expression::<T, _>(key), PhantomData)
+    }
+}
@@ -0,0 +1,102 @@
+use std::{collections::HashMap, hash::Hash, marker::PhantomData};
+
+use super::element_expression;
+use crate::{
+    Expression, MapContainsKeyExpression, MapEntriesExpression, MapGetExpression,
+    MapInsertExpression, MapKeysExpression, MapRemoveExpression, MapValuesExpression, Schema,
+    TransformValuesExpression,
+};
+
+/// Keyed lookups and in place mutations of a map.
+///
+/// Entries are iterated in an order that only depends on the operations
+/// applied to the map, not in the order of the client side
+/// [`HashMap`].
+pub trait HashMapOperators<K: Schema + Send + Sync + Eq + Hash, V: Schema + Send + Sync>:
+    Expression<Target = HashMap<K, V>> + Sized
+{
+    /// Value of `key`, if there is one.
+    fn get<Ke: Expression<Target = K>>(self, key: Ke) -> MapGetExpression<Self, Ke, V>;
+
+    /// Insert an entry, returning the previous value of `key` if there was one.
+    fn insert<Ke: Expression<Target = K>, Va: Expression<Target = V>>(
+        self,
+        key: Ke,
+        value: Va,
+    ) -> MapInsertExpression<Self, Ke, Va, V>;
+
+    /// Remove the entry of `key`, returning its value if there was one.
+    fn remove<Ke: Expression<Target = K>>(self, key: Ke) -> MapRemoveExpression<Self, Ke, V>;
+
+    fn contains_key<Ke: Expression<Target = K>>(
+        self,
+        key: Ke,
+    ) -> MapContainsKeyExpression<Self, Ke>;
+
+    fn keys(self) -> MapKeysExpression<Self, K>;
+
+    fn values(self) -> MapValuesExpression<Self, V>;
+
+    fn entries(self) -> MapEntriesExpression<Self, (K, V)>;
+
+    /// Map with the same keys whose values are given by `map`, for example
+    /// `groups.map_values(|group| group.length())`.
+    fn map_values<R: Expression>(
+        self,
+        map: impl FnOnce(V::Expression) -> R,
+    ) -> TransformValuesExpression<Self, R, HashMap<K, R::Target>>
+    where
+        R::Target: Send + Sync;
+}
+
+impl<
+        K: Schema + Send + Sync + Eq + Hash,
+        V: Schema + Send + Sync,
+        E: Expression<Target = HashMap<K, V>>,
+    > HashMapOperators<K, V> for E
+{
+    fn get<Ke: Expression<Target = K>>(self, key: Ke) -> MapGetExpression<Self, Ke, V> {
+        MapGetExpression(self, key, PhantomData)
+    }
+
+    fn insert<Ke: Expression<Target = K>, Va: Expression<Target = V>>(
+        self,
+        key: Ke,
+        value: Va,
+    ) -> MapInsertExpression<Self, Ke, Va, V> {
+        MapInsertExpression(self, key, value, PhantomData)
+    }
+
+    fn remove<Ke: Expression<Target = K>>(self, key: Ke) -> MapRemoveExpression<Self, Ke, V> {
+        MapRemoveExpression(self, key, PhantomData)
+    }
+
+    fn contains_key<Ke: Expression<Target = K>>(
+        self,
+        key: Ke,
+    ) -> MapContainsKeyExpression<Self, Ke> {
+        MapContainsKeyExpression(self, key)
+    }
+
+    fn keys(self) -> MapKeysExpression<Self, K> {
+        MapKeysExpression(self, PhantomData)
+    }
+
+    fn values(self) -> MapValuesExpression<Self, V> {
+        MapValuesExpression(self, PhantomData)
+    }
+
+    fn entries(self) -> MapEntriesExpression<Self, (K, V)> {
+        MapEntriesExpression(self, PhantomData)
+    }
+
+    fn map_values<R: Expression>(
+        self,
+        map: impl FnOnce(V::Expression) -> R,
+    ) -> TransformValuesExpression<Self, R, HashMap<K, R::Target>>
+    where
+        R::Target: Send + Sync,
+    {
+        TransformValuesExpression(self, element_expression::<V, _>(map), PhantomData)
+    }
+}
@@ -0,0 +1,70 @@
+use std::{collections::HashSet, hash::Hash};
+
+use crate::{
+    Expression, Schema, SetContainsExpression, SetDifferenceExpression, SetInsertExpression,
+    SetIntersectionExpression, SetRemoveExpression, SetUnionExpression,
+};
+
+/// Membership tests, in place mutations and combinations of sets.
+///
+/// Elements are compared by value, see [`Equal`](crate::Equal).
+pub trait HashSetOperators<T: Schema + Send + Sync + Eq + Hash>:
+    Expression<Target = HashSet<T>> + Sized
+{
+    fn contains<R: Expression<Target = T>>(self, value: R) -> SetContainsExpression<Self, R>;
+
+    /// Insert a value, returning whether it wasn't already in the set.
+    fn insert<R: Expression<Target = T>>(self, value: R) -> SetInsertExpression<Self, R>;
+
+    /// Remove a value, returning whether it was in the set.
+    fn remove<R: Expression<Target = T>>(self, value: R) -> SetRemoveExpression<Self, R>;
+
+    /// Elements of either set.
+    fn union<R: Expression<Target = HashSet<T>>>(self, other: R) -> SetUnionExpression<Self, R>;
+
+    /// Elements of both sets.
+    fn intersection<R: Expression<Target = HashSet<T>>>(
+        self,
+        other: R,
+    ) -> SetIntersectionExpression<Self, R>;
+
+    /// Elements of this set that aren't in `other`.
+    fn difference<R: Expression<Target = HashSet<T>>>(
+        self,
+        other: R,
+    ) -> SetDifferenceExpression<Self, R>;
+}
+
+impl<T: Schema + Send + Sync + Eq + Hash, E: Expression<Target = HashSet<T>>> HashSetOperators<T>
+    for E
+{
+    fn contains<R: Expression<Target = T>>(self, value: R) -> SetContainsExpression<Self, R> {
+        SetContainsExpression(self, value)
+    }
+
+    fn insert<R: Expression<Target = T>>(self, value: R) -> SetInsertExpression<Self, R> {
+        SetInsertExpression(self, value)
+    }
+
+    fn remove<R: Expression<Target = T>>(self, value: R) -> SetRemoveExpression<Self, R> {
+        SetRemoveExpression(self, value)
+    }
+
+    fn union<R: Expression<Target = HashSet<T>>>(self, other: R) -> SetUnionExpression<Self, R> {
+        SetUnionExpression(self, other)
+    }
+
+    fn intersection<R: Expression<Target = HashSet<T>>>(
+        self,
+        other: R,
+    ) -> SetIntersectionExpression<Self, R> {
+        SetIntersectionExpression(self, other)
+    }
+
+    fn difference<R: Expression<Target = HashSet<T>>>(
+        self,
+        other: R,
+    ) -> SetDifferenceExpression<Self, R> {
+        SetDifferenceExpression(self, other)
+    }
+}
@@ -0,0 +1,41 @@
+use std::marker::PhantomData;
+
+use super::element_expression;
+use crate::{Expression, JoinExpression, Key, Schema, SlotMap};
+
+/// Resolution of the [`Key`]s carried by the elements of a list.
+pub trait Join<T: Schema + Send + Sync>: Expression<Target = Vec<T>> + Sized {
+    /// Pair of each element with the value its key resolves to in `slot_map`,
+    /// or `None` if the key doesn't resolve anymore, for example
+    /// `orders.join(|order| order.customer, customers)`.
+    ///
+    /// `slot_map` is evaluated once and keys are resolved by indexing its
+    /// slots, so the join takes a time linear in the length of the list
+    /// whatever the size of the map.
+    fn join<K, U, Ke, M>(
+        self,
+        key: impl FnOnce(T::Expression) -> Ke,
+        slot_map: M,
+    ) -> JoinExpression<Self, Ke, M, (T, Option<U>)>
+    where
+        K: Key + Send + Sync,
+        U: Schema + Send + Sync,
+        Ke: Expression<Target = K>,
+        M: Expression<Target = SlotMap<K, U>>;
+}
+
+impl<T: Schema + Send + Sync, E: Expression<Target = Vec<T>>> Join<T> for E {
+    fn join<K, U, Ke, M>(
+        self,
+        key: impl FnOnce(T::Expression) -> Ke,
+        slot_map: M,
+    ) -> JoinExpression<Self, Ke, M, (T, Option<U>)>
+    where
+        K: Key + Send + Sync,
+        U: Schema + Send + Sync,
+        Ke: Expression<Target = K>,
+        M: Expression<Target = SlotMap<K, U>>,
+    {
+        JoinExpression(self, element_expression::<T, _>(key), slot_map, PhantomData)
+    }
+}
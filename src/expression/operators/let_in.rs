@@ -0,0 +1,33 @@
+use super::element_expression;
+use crate::{Expression, FromPath, LetExpression, PathExpression, Schema, Scope};
+
+/// Value of `body` given the value of `value`, which is evaluated once however
+/// many times `body` uses it.
+///
+/// Unlike cloning an expression, which evaluates it again wherever the clone
+/// is used, binding a mutating expression runs its mutation once.
+pub fn let_in<V: Expression, R: Expression>(
+    value: V,
+    body: impl FnOnce(<V::Target as Schema>::Expression) -> R,
+) -> LetExpression<V, R>
+where
+    V::Target: Schema,
+{
+    LetExpression(value, element_expression::<V::Target, _>(body))
+}
+
+/// [`let_in`] whose body is given the bound value as a [`PathExpression`],
+/// which can be cloned whatever the schema of the value.
+pub(crate) fn let_path<V: Expression, R: Expression>(
+    value: V,
+    body: impl FnOnce(PathExpression<V::Target>) -> R,
+) -> LetExpression<V, R>
+where
+    V::Target: Schema,
+{
+    Scope::increment_depth();
+    let body = (body)(PathExpression::from_path(vec![Scope::get().unwrap()]));
+    Scope::decrement_depth();
+
+    LetExpression(value, body)
+}
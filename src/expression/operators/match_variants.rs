@@ -0,0 +1,137 @@
+use std::{future::Future, io, marker::PhantomData};
+
+use tokio::io::AsyncWriteExt;
+
+use crate::{
+    expression_discriminant, Expression, IsVariantExpression, MatchExpression, Schema, Scope,
+    VariantExpression,
+};
+
+/// Arms of a `match_variants` expression, generated by
+/// [`#[derive(Schema)]`](crate::Schema) for enums.
+///
+/// Arms are a list of the form `(arm_0, (arm_1, ... (arm_n, ())))`, one arm
+/// per variant in the order of the variants, all evaluating to `Out`.
+pub trait MatchArms<Out: Schema> {
+    /// Number of arms.
+    const LENGTH: u32;
+
+    fn write_arms(
+        self,
+        write: &mut (impl AsyncWriteExt + Unpin + Send),
+    ) -> impl Future<Output = io::Result<()>>;
+}
+
+impl<Out: Schema> MatchArms<Out> for () {
+    const LENGTH: u32 = 0;
+
+    fn write_arms(
+        self,
+        _write: &mut (impl AsyncWriteExt + Unpin + Send),
+    ) -> impl Future<Output = io::Result<()>> {
+        async { Ok(()) }
+    }
+}
+
+impl<Out: Schema, R: Expression<Target = Out>, Rest: MatchArms<Out>> MatchArms<Out> for (R, Rest) {
+    const LENGTH: u32 = 1 + Rest::LENGTH;
+
+    fn write_arms(
+        self,
+        write: &mut (impl AsyncWriteExt + Unpin + Send),
+    ) -> impl Future<Output = io::Result<()>> {
+        async move {
+            Box::pin(self.0.write(write)).await?;
+            Box::pin(self.1.write_arms(write)).await
+        }
+    }
+}
+
+/// Fields of the value of a variant built by a constructor generated by
+/// [`#[derive(Schema)]`](crate::Schema) for enums.
+///
+/// Fields are a list of the form `(field_0, (field_1, ... (field_n, ())))`,
+/// a variant without fields has the unit value.
+pub trait VariantFields: Sized {
+    /// Number of fields.
+    const LENGTH: u32;
+
+    /// Write the value of the variant, a product of its fields.
+    fn write_value(
+        self,
+        write: &mut (impl AsyncWriteExt + Unpin + Send),
+    ) -> impl Future<Output = io::Result<()>> {
+        async move {
+            if Self::LENGTH == 0 {
+                write.write_u8(expression_discriminant::VALUE).await?;
+                <() as Schema>::write_schema(write).await
+            } else {
+                write.write_u8(expression_discriminant::PRODUCT).await?;
+                write.write_u32(Self::LENGTH).await?;
+                self.write_fields(write).await
+            }
+        }
+    }
+
+    fn write_fields(
+        self,
+        write: &mut (impl AsyncWriteExt + Unpin + Send),
+    ) -> impl Future<Output = io::Result<()>>;
+}
+
+impl VariantFields for () {
+    const LENGTH: u32 = 0;
+
+    fn write_fields(
+        self,
+        _write: &mut (impl AsyncWriteExt + Unpin + Send),
+    ) -> impl Future<Output = io::Result<()>> {
+        async { Ok(()) }
+    }
+}
+
+impl<F: Expression, Rest: VariantFields> VariantFields for (F, Rest) {
+    const LENGTH: u32 = 1 + Rest::LENGTH;
+
+    fn write_fields(
+        self,
+        write: &mut (impl AsyncWriteExt + Unpin + Send),
+    ) -> impl Future<Output = io::Result<()>> {
+        async move {
+            Box::pin(self.0.write(write)).await?;
+            Box::pin(self.1.write_fields(write)).await
+        }
+    }
+}
+
+/// Arm built by `arm` from the path of the value of its variant.
+#[doc(hidden)]
+pub fn variant_arm<R>(arm: impl FnOnce(Vec<u32>) -> R) -> R {
+    Scope::increment_depth();
+    let expression = (arm)(vec![Scope::get().unwrap()]);
+    Scope::decrement_depth();
+
+    expression
+}
+
+#[doc(hidden)]
+pub fn match_variants<L: Expression, A: MatchArms<Out>, Out: Schema>(
+    sum: L,
+    arms: A,
+) -> MatchExpression<L, A, Out> {
+    MatchExpression(sum, arms, PhantomData)
+}
+
+/// Value of the variant with the given discriminant, see [`VariantFields`].
+#[doc(hidden)]
+pub fn variant<F: VariantFields, Out: Schema>(
+    discriminant: u32,
+    fields: F,
+) -> VariantExpression<F, Out> {
+    VariantExpression(discriminant, fields, PhantomData)
+}
+
+#[doc(hidden)]
+pub fn is_variant<L: Expression>(sum: L, discriminant: u32) -> IsVariantExpression<L> {
+    IsVariantExpression(sum, discriminant)
+}
@@ -1,33 +1,68 @@
+mod aggregate;
 mod and;
+mod arithmetic;
 mod chain;
+mod compare;
 mod condition;
 mod equal;
 mod filter;
 mod get;
+mod group_by;
+mod hash_map;
+mod hash_set;
 mod insert;
+mod join;
 mod length;
+mod let_in;
 mod map;
+mod match_variants;
 mod option;
+mod or;
+mod paginate;
 mod set;
 mod slot_map;
+mod sort;
+mod string;
+mod vec;
+mod xor;
 
 pub use self::{
+    aggregate::Aggregate,
     and::And,
+    arithmetic::{Arithmetic, Numeric},
     chain::Chain,
+    compare::{Compare, Ordered},
     condition::BoolOperators,
-    equal::{
-        Int128Equal, Int16Equal, Int32Equal, Int64Equal, Int8Equal, NonZeroInt128Equal,
-        NonZeroInt16Equal, NonZeroInt32Equal, NonZeroInt64Equal, NonZeroInt8Equal,
-        NonZeroUint128Equal, NonZeroUint16Equal, NonZeroUint32Equal, NonZeroUint64Equal,
-        NonZeroUint8Equal, StringEqual, Uint128Equal, Uint16Equal, Uint32Equal, Uint64Equal,
-        Uint8Equal,
-    },
+    equal::Equal,
     filter::{HashSetFilter, VecFilter},
     get::VecGet,
+    group_by::GroupBy,
+    hash_map::HashMapOperators,
+    hash_set::HashSetOperators,
     insert::VecInsert,
+    join::Join,
     length::Length,
+    let_in::let_in,
     map::MapVec,
+    match_variants::{is_variant, match_variants, variant, variant_arm, MatchArms, VariantFields},
     option::{FlattenOperator, OptionOperators},
+    or::Or,
+    paginate::Paginate,
     set::{Set, SetIfSome},
     slot_map::SlotMapOperators,
+    sort::{Ascending, Descending, Sort, SortDirection, SortKey},
+    string::StringOperators,
+    vec::VecOperators,
+    xor::Xor,
 };
+
+use crate::{FromPath, Schema, Scope};
+
+/// Expression built by `closure` for an element of a list.
+fn element_expression<T: Schema, R>(closure: impl FnOnce(T::Expression) -> R) -> R {
+    Scope::increment_depth();
+    let expression = (closure)(T::Expression::from_path(vec![Scope::get().unwrap()]));
+    Scope::decrement_depth();
+
+    expression
+}
@@ -0,0 +1,11 @@
+use crate::{Expression, OrExpression};
+
+pub trait Or<Rhs: Expression>: Expression + Sized {
+    fn or(self, rhs: Rhs) -> OrExpression<Self, Rhs>;
+}
+
+impl<Lhs: Expression<Target = bool>, Rhs: Expression<Target = bool>> Or<Rhs> for Lhs {
+    fn or(self, rhs: Rhs) -> OrExpression<Self, Rhs> {
+        OrExpression(self, rhs)
+    }
+}
@@ -0,0 +1,24 @@
+use crate::{Expression, Schema, SkipExpression, TakeExpression};
+
+/// Pages of a list, for example `list.skip(20).take(10)` for its third page
+/// of ten elements.
+///
+/// See [`SlotMapOperators::page`](crate::SlotMapOperators::page) for pages
+/// which stay stable while the collection is modified.
+pub trait Paginate<T: Schema + Send + Sync>: Expression<Target = Vec<T>> + Sized {
+    /// Elements after the first `count` ones.
+    fn skip<I: Expression<Target = u32>>(self, count: I) -> SkipExpression<Self, I>;
+
+    /// First `count` elements, or all of them if there are less.
+    fn take<I: Expression<Target = u32>>(self, count: I) -> TakeExpression<Self, I>;
+}
+
+impl<T: Schema + Send + Sync, E: Expression<Target = Vec<T>>> Paginate<T> for E {
+    fn skip<I: Expression<Target = u32>>(self, count: I) -> SkipExpression<Self, I> {
+        SkipExpression(self, count)
+    }
+
+    fn take<I: Expression<Target = u32>>(self, count: I) -> TakeExpression<Self, I> {
+        TakeExpression(self, count)
+    }
+}
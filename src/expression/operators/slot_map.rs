@@ -1,67 +1,89 @@
 use std::{marker::PhantomData, num::NonZeroU32};
 
 use crate::{
-    BoolOperators, Expression, GetExpression, Key, NonZeroUint32Equal, OptionOperators,
-    PathExpression, Schema, SlotMap,
+    BoolOperators, Equal, Expression, GetExpression, Key, OptionOperators, PathExpression, Schema,
+    SlotMap, SlotMapInsertExpression, SlotMapPageExpression, SlotMapRemoveExpression,
 };
 
-use super::{FlattenOperator, Set};
+use super::{let_in::let_path, FlattenOperator};
 
-pub trait SlotMapOperators<K: Key, Ke: Expression<Target = K>, T: Schema + Send + Sync> {
-    fn get(self, key: Ke) -> impl Expression<Target = Option<T>>;
+pub trait SlotMapOperators<K: Key, T: Schema + Send + Sync> {
+    fn get<Ke: Expression<Target = K>>(self, key: Ke) -> impl Expression<Target = Option<T>>;
 
-    fn remove(self, key: Ke) -> impl Expression<Target = Option<T>>;
+    /// Remove the value of `key` if it is still in the map, its slot then gets
+    /// a new generation so `key` never resolves again.
+    fn remove<Ke: Expression<Target = K>>(self, key: Ke) -> impl Expression<Target = Option<T>>;
 
-    // fn insert(self, value: T) -> impl Expression<Target = K>;
+    /// Insert a value in a free slot, or in a new one if there is none,
+    /// returning its key.
+    fn insert<R: Expression<Target = T>>(self, value: R) -> impl Expression<Target = K>;
+
+    /// Page of at most `limit` entries, the first ones whose key is after
+    /// `after` or from the start if it is `None`.
+    ///
+    /// Keys are ordered by index then generation, so passing the key of the
+    /// last entry of a page gives the next one. Inserting or removing entries
+    /// between two pages neither repeats nor skips the entries left in the map
+    /// throughout, an entry inserted in a slot before the cursor is only
+    /// missed.
+    fn page<C: Expression<Target = Option<K>>, I: Expression<Target = u32>>(
+        self,
+        after: C,
+        limit: I,
+    ) -> impl Expression<Target = Vec<(K, T)>>
+    where
+        K: Schema + Send + Sync;
 }
 
 impl<
-        K: Key,
-        Ke: Expression<Target = K> + Clone,
+        K: Key + Schema,
         T: Schema + Expression<Target = T> + Send + Sync,
         E: Expression<Target = SlotMap<K, T>>,
-    > SlotMapOperators<K, Ke, T> for E
+    > SlotMapOperators<K, T> for E
 {
-    fn get(self, key: Ke) -> impl Expression<Target = Option<T>> {
-        let key_index = GetExpression::<Ke, u32, u32>(key.clone(), 0, PhantomData);
-        let key_generation = GetExpression::<Ke, u32, NonZeroU32>(key, 1, PhantomData);
+    fn get<Ke: Expression<Target = K>>(self, key: Ke) -> impl Expression<Target = Option<T>> {
+        // The key is bound so it is evaluated once for both of its fields.
+        let_path(key, |key| {
+            let key_index =
+                GetExpression::<PathExpression<K>, u32, u32>(key.clone(), 0, PhantomData);
+            let key_generation =
+                GetExpression::<PathExpression<K>, u32, NonZeroU32>(key, 1, PhantomData);
 
-        let get_result = GetExpression::<
-            Self,
-            GetExpression<Ke, u32, u32>,
-            Option<(NonZeroU32, Option<T>)>,
-        >(self, key_index, PhantomData);
+            let slots =
+                GetExpression::<Self, u32, Vec<(NonZeroU32, Option<T>)>>(self, 0, PhantomData);
 
-        get_result
-            .map(|slot| {
-                slot.0
-                    .equal(key_generation)
-                    .if_else::<Option<T>, PathExpression<Option<T>>, Option<T>>(slot.1, None)
-            })
-            .flatten()
-    }
+            let get_result = GetExpression::<
+                GetExpression<Self, u32, Vec<(NonZeroU32, Option<T>)>>,
+                GetExpression<PathExpression<K>, u32, u32>,
+                Option<(NonZeroU32, Option<T>)>,
+            >(slots, key_index, PhantomData);
 
-    fn remove(self, key: Ke) -> impl Expression<Target = Option<T>> {
-        let key_index = GetExpression::<Ke, u32, u32>(key.clone(), 0, PhantomData);
-        let key_generation = GetExpression::<Ke, u32, NonZeroU32>(key, 1, PhantomData);
+            get_result
+                .map(|slot| {
+                    slot.0
+                        .equal(key_generation)
+                        .if_else::<Option<T>, PathExpression<Option<T>>, Option<T>>(slot.1, None)
+                })
+                .flatten()
+        })
+    }
 
-        let get_result = GetExpression::<
-            Self,
-            GetExpression<Ke, u32, u32>,
-            Option<(NonZeroU32, Option<T>)>,
-        >(self, key_index, PhantomData);
+    fn remove<Ke: Expression<Target = K>>(self, key: Ke) -> impl Expression<Target = Option<T>> {
+        SlotMapRemoveExpression(self, key, PhantomData)
+    }
 
-        get_result
-            .map(|slot| {
-                slot.0
-                    .clone()
-                    .equal(key_generation)
-                    .if_else::<Option<T>, _, Option<T>>(slot.1.set(Option::<T>::None), None)
-            })
-            .flatten()
+    fn insert<R: Expression<Target = T>>(self, value: R) -> impl Expression<Target = K> {
+        SlotMapInsertExpression(self, value, PhantomData)
     }
 
-    // fn insert(self, value: T) -> impl Expression<Target = K> {
-    //     todo!()
-    // }
+    fn page<C: Expression<Target = Option<K>>, I: Expression<Target = u32>>(
+        self,
+        after: C,
+        limit: I,
+    ) -> impl Expression<Target = Vec<(K, T)>>
+    where
+        K: Send + Sync,
+    {
+        SlotMapPageExpression(self, after, limit, PhantomData)
+    }
 }
@@ -0,0 +1,215 @@
+use std::{future::Future, io};
+
+use tokio::io::AsyncWriteExt;
+
+use super::element_expression;
+use crate::{
+    Expression, Ordered, ReverseExpression, ReversedExpression, Schema, SortExpression,
+    SortedExpression,
+};
+
+/// Keys a list is sorted by, see [`Sort::sort_by_key`].
+///
+/// An ordered expression is an ascending key, [`SortDirection`] gives its
+/// direction explicitly. A tuple of keys sorts by its first key, then by the
+/// following ones to break ties.
+pub trait SortKey {
+    /// Number of keys.
+    const LENGTH: u32;
+
+    /// Write each key as its direction, `0` for ascending and `1` for
+    /// descending, followed by its expression.
+    fn write_keys(
+        self,
+        write: &mut (impl AsyncWriteExt + Unpin + Send),
+    ) -> impl Future<Output = io::Result<()>>;
+}
+
+/// Key sorted from the least to the greatest value.
+pub struct Ascending<E: Expression>(pub(crate) E);
+
+/// Key sorted from the greatest to the least value.
+pub struct Descending<E: Expression>(pub(crate) E);
+
+/// Direction of a [`SortKey`].
+pub trait SortDirection: Expression + Sized {
+    fn ascending(self) -> Ascending<Self>;
+    fn descending(self) -> Descending<Self>;
+}
+
+impl<E: Expression> SortDirection for E
+where
+    E::Target: Ordered,
+{
+    fn ascending(self) -> Ascending<Self> {
+        Ascending(self)
+    }
+
+    fn descending(self) -> Descending<Self> {
+        Descending(self)
+    }
+}
+
+impl<E: Expression> SortKey for E
+where
+    E::Target: Ordered,
+{
+    const LENGTH: u32 = 1;
+
+    fn write_keys(
+        self,
+        write: &mut (impl AsyncWriteExt + Unpin + Send),
+    ) -> impl Future<Output = io::Result<()>> {
+        Ascending(self).write_keys(write)
+    }
+}
+
+impl<E: Expression> SortKey for Ascending<E> {
+    const LENGTH: u32 = 1;
+
+    fn write_keys(
+        self,
+        write: &mut (impl AsyncWriteExt + Unpin + Send),
+    ) -> impl Future<Output = io::Result<()>> {
+        async move {
+            write.write_u8(0).await?;
+            Box::pin(self.0.write(write)).await?;
+            Ok(())
+        }
+    }
+}
+
+impl<E: Expression> SortKey for Descending<E> {
+    const LENGTH: u32 = 1;
+
+    fn write_keys(
+        self,
+        write: &mut (impl AsyncWriteExt + Unpin + Send),
+    ) -> impl Future<Output = io::Result<()>> {
+        async move {
+            write.write_u8(1).await?;
+            Box::pin(self.0.write(write)).await?;
+            Ok(())
+        }
+    }
+}
+
+/// No key, the elements themselves are sorted in ascending order.
+impl SortKey for () {
+    const LENGTH: u32 = 0;
+
+    fn write_keys(
+        self,
+        _write: &mut (impl AsyncWriteExt + Unpin + Send),
+    ) -> impl Future<Output = io::Result<()>> {
+        async { Ok(()) }
+    }
+}
+
+macro_rules! impl_sort_key {
+    ($($($key:ident)*;)*) => {
+        $(
+            impl<$($key: SortKey,)*> SortKey for ($($key,)*) {
+                const LENGTH: u32 = 0 $(+ $key::LENGTH)*;
+
+                fn write_keys(
+                    self,
+                    write: &mut (impl AsyncWriteExt + Unpin + Send),
+                ) -> impl Future<Output = io::Result<()>> {
+                    async move {
+                        #[allow(non_snake_case)]
+                        let ($($key,)*) = self;
+                        $(Box::pin($key.write_keys(write)).await?;)*
+                        Ok(())
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_sort_key!(
+    A;
+    A B;
+    A B C;
+    A B C D;
+    A B C D E;
+    A B C D E F;
+    A B C D E F G;
+    A B C D E F G H;
+);
+
+/// Sorting and reversal of a list, either in place or into a new list.
+///
+/// Sorts are stable, elements with equal keys keep their order. Unlike
+/// [`Compare`](crate::Compare), floats are ordered by their `total_cmp`
+/// method, so a NaN is greater than any other float.
+pub trait Sort<T: Schema + Send + Sync>: Expression<Target = Vec<T>> + Sized {
+    /// Sort the list in place in ascending order.
+    fn sort(self) -> SortExpression<Self, ()>
+    where
+        T: Ordered;
+
+    /// Sort the list in place by the keys of its elements, for example
+    /// `users.sort_by_key(|user| (user.age.descending(), user.name))`.
+    fn sort_by_key<K: SortKey>(
+        self,
+        key: impl FnOnce(T::Expression) -> K,
+    ) -> SortExpression<Self, K>;
+
+    /// Reverse the order of the list in place.
+    fn reverse(self) -> ReverseExpression<Self>;
+
+    /// Copy of the list sorted in ascending order.
+    fn sorted(self) -> SortedExpression<Self, ()>
+    where
+        T: Ordered;
+
+    /// Copy of the list sorted by the keys of its elements, see
+    /// [`Sort::sort_by_key`].
+    fn sorted_by_key<K: SortKey>(
+        self,
+        key: impl FnOnce(T::Expression) -> K,
+    ) -> SortedExpression<Self, K>;
+
+    /// Copy of the list in reverse order.
+    fn reversed(self) -> ReversedExpression<Self>;
+}
+
+impl<T: Schema + Send + Sync, E: Expression<Target = Vec<T>>> Sort<T> for E {
+    fn sort(self) -> SortExpression<Self, ()>
+    where
+        T: Ordered,
+    {
+        SortExpression(self, ())
+    }
+
+    fn sort_by_key<K: SortKey>(
+        self,
+        key: impl FnOnce(T::Expression) -> K,
+    ) -> SortExpression<Self, K> {
+        SortExpression(self, element_expression::<T, _>(key))
+    }
+
+    fn reverse(self) -> ReverseExpression<Self> {
+        ReverseExpression(self)
+    }
+
+    fn sorted(self) -> SortedExpression<Self, ()>
+    where
+        T: Ordered,
+    {
+        SortedExpression(self, ())
+    }
+
+    fn sorted_by_key<K: SortKey>(
+        self,
+        key: impl FnOnce(T::Expression) -> K,
+    ) -> SortedExpression<Self, K> {
+        SortedExpression(self, element_expression::<T, _>(key))
+    }
+
+    fn reversed(self) -> ReversedExpression<Self> {
+        ReversedExpression(self)
+    }
+}
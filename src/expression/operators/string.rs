@@ -0,0 +1,94 @@
+use crate::{
+    ConcatExpression, EndsWithExpression, Expression, MatchesExpression, Pattern,
+    StartsWithExpression, StringContainsExpression, StringLengthExpression, SubstringExpression,
+    ToLowercaseExpression, ToUppercaseExpression,
+};
+
+/// Operations on strings.
+///
+/// Lengths and positions count characters (unicode scalar values), not bytes,
+/// so a string is never split inside a character.
+pub trait StringOperators: Expression<Target = String> + Sized {
+    fn concat<R: Expression<Target = String>>(self, rhs: R) -> ConcatExpression<Self, R>;
+
+    fn contains<R: Expression<Target = String>>(
+        self,
+        pattern: R,
+    ) -> StringContainsExpression<Self, R>;
+
+    fn starts_with<R: Expression<Target = String>>(
+        self,
+        prefix: R,
+    ) -> StartsWithExpression<Self, R>;
+
+    fn ends_with<R: Expression<Target = String>>(self, suffix: R) -> EndsWithExpression<Self, R>;
+
+    /// Number of characters of the string.
+    fn length(self) -> StringLengthExpression<Self>;
+
+    /// The `length` characters starting at the character `start`, or less if
+    /// the string ends before.
+    fn substring<S: Expression<Target = u32>, N: Expression<Target = u32>>(
+        self,
+        start: S,
+        length: N,
+    ) -> SubstringExpression<Self, S, N>;
+
+    fn to_lowercase(self) -> ToLowercaseExpression<Self>;
+
+    fn to_uppercase(self) -> ToUppercaseExpression<Self>;
+
+    /// Whether the string matches `pattern`.
+    ///
+    /// The pattern is compiled once per query by the server, an invalid one
+    /// fails with [`QueryError::InvalidPattern`](crate::QueryError::InvalidPattern).
+    fn matches(self, pattern: Pattern) -> MatchesExpression<Self>;
+}
+
+impl<E: Expression<Target = String>> StringOperators for E {
+    fn concat<R: Expression<Target = String>>(self, rhs: R) -> ConcatExpression<Self, R> {
+        ConcatExpression(self, rhs)
+    }
+
+    fn contains<R: Expression<Target = String>>(
+        self,
+        pattern: R,
+    ) -> StringContainsExpression<Self, R> {
+        StringContainsExpression(self, pattern)
+    }
+
+    fn starts_with<R: Expression<Target = String>>(
+        self,
+        prefix: R,
+    ) -> StartsWithExpression<Self, R> {
+        StartsWithExpression(self, prefix)
+    }
+
+    fn ends_with<R: Expression<Target = String>>(self, suffix: R) -> EndsWithExpression<Self, R> {
+        EndsWithExpression(self, suffix)
+    }
+
+    fn length(self) -> StringLengthExpression<Self> {
+        StringLengthExpression(self)
+    }
+
+    fn substring<S: Expression<Target = u32>, N: Expression<Target = u32>>(
+        self,
+        start: S,
+        length: N,
+    ) -> SubstringExpression<Self, S, N> {
+        SubstringExpression(self, start, length)
+    }
+
+    fn to_lowercase(self) -> ToLowercaseExpression<Self> {
+        ToLowercaseExpression(self)
+    }
+
+    fn to_uppercase(self) -> ToUppercaseExpression<Self> {
+        ToUppercaseExpression(self)
+    }
+
+    fn matches(self, pattern: Pattern) -> MatchesExpression<Self> {
+        MatchesExpression(self, pattern)
+    }
+}
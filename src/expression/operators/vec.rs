@@ -0,0 +1,55 @@
+use std::marker::PhantomData;
+
+use crate::{
+    ClearExpression, Expression, ExtendExpression, PopExpression, PushExpression, RemoveExpression,
+    Schema, TruncateExpression,
+};
+
+/// In place mutations of a list.
+pub trait VecOperators<T: Schema + Send + Sync>: Expression<Target = Vec<T>> + Sized {
+    /// Append a value, returning the new length of the list.
+    fn push<R: Expression<Target = T>>(self, value: R) -> PushExpression<Self, R>;
+
+    /// Remove the last element, returning it if the list wasn't empty.
+    fn pop(self) -> PopExpression<Self, T>;
+
+    /// Remove the element at `index`, returning it. Fails with
+    /// [`QueryError::IndexOutOfBounds`](crate::QueryError::IndexOutOfBounds) if
+    /// there is no such element.
+    fn remove<I: Expression<Target = u32>>(self, index: I) -> RemoveExpression<Self, I, T>;
+
+    /// Remove all the elements, returning them.
+    fn clear(self) -> ClearExpression<Self>;
+
+    /// Remove the elements after the first `length` ones, returning them.
+    fn truncate<I: Expression<Target = u32>>(self, length: I) -> TruncateExpression<Self, I>;
+
+    /// Append all the elements of `values`, returning the new length of the list.
+    fn extend<R: Expression<Target = Vec<T>>>(self, values: R) -> ExtendExpression<Self, R>;
+}
+
+impl<T: Schema + Send + Sync, E: Expression<Target = Vec<T>>> VecOperators<T> for E {
+    fn push<R: Expression<Target = T>>(self, value: R) -> PushExpression<Self, R> {
+        PushExpression(self, value)
+    }
+
+    fn pop(self) -> PopExpression<Self, T> {
+        PopExpression(self, PhantomData)
+    }
+
+    fn remove<I: Expression<Target = u32>>(self, index: I) -> RemoveExpression<Self, I, T> {
+        RemoveExpression(self, index, PhantomData)
+    }
+
+    fn clear(self) -> ClearExpression<Self> {
+        ClearExpression(self)
+    }
+
+    fn truncate<I: Expression<Target = u32>>(self, length: I) -> TruncateExpression<Self, I> {
+        TruncateExpression(self, length)
+    }
+
+    fn extend<R: Expression<Target = Vec<T>>>(self, values: R) -> ExtendExpression<Self, R> {
+        ExtendExpression(self, values)
+    }
+}
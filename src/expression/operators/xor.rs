@@ -0,0 +1,11 @@
+use crate::{Expression, XorExpression};
+
+pub trait Xor<Rhs: Expression>: Expression + Sized {
+    fn xor(self, rhs: Rhs) -> XorExpression<Self, Rhs>;
+}
+
+impl<Lhs: Expression<Target = bool>, Rhs: Expression<Target = bool>> Xor<Rhs> for Lhs {
+    fn xor(self, rhs: Rhs) -> XorExpression<Self, Rhs> {
+        XorExpression(self, rhs)
+    }
+}
@@ -0,0 +1,203 @@
+use std::{io, sync::Arc};
+
+use regex::Regex;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+use crate::{io_error, QueryError};
+
+/// Pattern a string is matched against, see
+/// [`StringOperators::matches`](crate::StringOperators::matches).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Pattern {
+    /// SQL `LIKE` pattern matching the whole string: `%` matches any sequence
+    /// of characters and `_` any single character. A `\` matches the
+    /// character following it literally.
+    Like(String),
+    /// Glob pattern matching the whole string: `*` matches any sequence of
+    /// characters, `?` any single character, `[abc]` or `[a-z]` one of the
+    /// characters of the set and `[!abc]` any character out of it. A `\`
+    /// matches the character following it literally.
+    Glob(String),
+    /// Regular expression, with the syntax of the
+    /// [`regex`](https://docs.rs/regex) crate, matching anywhere in the string
+    /// unless it is anchored.
+    Regex(String),
+}
+
+pub mod pattern_discriminant {
+    pub const LIKE: u8 = 0;
+    pub const GLOB: u8 = 1;
+    pub const REGEX: u8 = 2;
+}
+
+impl Pattern {
+    fn discriminant(&self) -> u8 {
+        match self {
+            Self::Like(_) => pattern_discriminant::LIKE,
+            Self::Glob(_) => pattern_discriminant::GLOB,
+            Self::Regex(_) => pattern_discriminant::REGEX,
+        }
+    }
+
+    fn source(&self) -> &str {
+        match self {
+            Self::Like(source) | Self::Glob(source) | Self::Regex(source) => source,
+        }
+    }
+
+    /// Equivalent regular expression.
+    fn to_regex(&self) -> Result<String, QueryError> {
+        let invalid_pattern = |reason: &str| QueryError::InvalidPattern(reason.to_string());
+
+        let source = match self {
+            Self::Regex(source) => return Ok(source.clone()),
+            Self::Like(source) | Self::Glob(source) => source,
+        };
+
+        let mut regex = String::from("^(?s:");
+        let mut chars = source.chars();
+
+        while let Some(char) = chars.next() {
+            match (self, char) {
+                (_, '\\') => push_literal(
+                    &mut regex,
+                    chars
+                        .next()
+                        .ok_or_else(|| invalid_pattern("pattern ends with an escape character"))?,
+                ),
+                (Self::Like(_), '%') | (Self::Glob(_), '*') => regex.push_str(".*"),
+                (Self::Like(_), '_') | (Self::Glob(_), '?') => regex.push('.'),
+                (Self::Glob(_), '[') => {
+                    regex.push('[');
+
+                    if chars.clone().next() == Some('!') {
+                        chars.next();
+                        regex.push('^');
+                    }
+
+                    let mut empty = true;
+                    loop {
+                        let char = chars
+                            .next()
+                            .ok_or_else(|| invalid_pattern("unclosed character set in glob"))?;
+
+                        match char {
+                            // A `]` or a `-` first in the set is a member of it.
+                            ']' if !empty => break,
+                            '-' if !empty => regex.push('-'),
+                            '\\' => push_literal(
+                                &mut regex,
+                                chars.next().ok_or_else(|| {
+                                    invalid_pattern("pattern ends with an escape character")
+                                })?,
+                            ),
+                            char => push_literal(&mut regex, char),
+                        }
+
+                        empty = false;
+                    }
+
+                    regex.push(']');
+                }
+                (_, char) => push_literal(&mut regex, char),
+            }
+        }
+
+        regex.push_str(")$");
+
+        Ok(regex)
+    }
+
+    /// Compile the pattern, see [`CompiledPattern`].
+    pub fn compile(self) -> CompiledPattern {
+        let regex = self.to_regex().and_then(|regex| {
+            Regex::new(&regex)
+                .map(Arc::new)
+                .map_err(|err| QueryError::InvalidPattern(err.to_string()))
+        });
+
+        CompiledPattern {
+            pattern: self,
+            regex,
+        }
+    }
+
+    pub async fn read(read: &mut (impl AsyncReadExt + Unpin)) -> io::Result<Self> {
+        let discriminant = read.read_u8().await?;
+
+        let length: usize = read.read_u32().await?.try_into().map_err(|_| {
+            io_error!(
+                OutOfMemory,
+                "pattern length doesn't fit into a pointer sized unsigned integer",
+            )
+        })?;
+
+        let mut source = Vec::new();
+        source
+            .try_reserve(length)
+            .map_err(|_| io_error!(OutOfMemory, "allocation of memory for pattern failed"))?;
+        source.resize(length, 0);
+
+        read.read_exact(&mut source).await?;
+
+        let source = String::from_utf8(source)
+            .map_err(|_| io_error!(InvalidData, "pattern isn't valid utf-8"))?;
+
+        let pattern = match discriminant {
+            pattern_discriminant::LIKE => Self::Like(source),
+            pattern_discriminant::GLOB => Self::Glob(source),
+            pattern_discriminant::REGEX => Self::Regex(source),
+            _ => {
+                return Err(io_error!(
+                    InvalidData,
+                    "invalid discriminant while parsing pattern",
+                ));
+            }
+        };
+
+        debug_assert_eq!(pattern.discriminant(), discriminant);
+
+        Ok(pattern)
+    }
+
+    pub async fn write(&self, write: &mut (impl AsyncWriteExt + Unpin)) -> io::Result<()> {
+        write.write_u8(self.discriminant()).await?;
+
+        write
+            .write_u32(self.source().len().try_into().map_err(|_| {
+                io_error!(
+                    OutOfMemory,
+                    "pattern length doesn't fit into a 32 bit unsigned integer",
+                )
+            })?)
+            .await?;
+        write.write_all(self.source().as_bytes()).await?;
+
+        Ok(())
+    }
+}
+
+fn push_literal(regex: &mut String, char: char) {
+    regex.push_str(&regex::escape(char.encode_utf8(&mut [0; 4])));
+}
+
+/// A [`Pattern`] compiled when its query is read, so it is compiled once
+/// however many strings it is matched against.
+///
+/// An invalid pattern is kept along with its error, which is reported when
+/// the query is type checked.
+#[derive(Debug, Clone)]
+pub struct CompiledPattern {
+    pattern: Pattern,
+    regex: Result<Arc<Regex>, QueryError>,
+}
+
+impl CompiledPattern {
+    pub fn pattern(&self) -> &Pattern {
+        &self.pattern
+    }
+
+    pub(crate) fn regex(&self) -> Result<&Regex, QueryError> {
+        self.regex.as_deref().map_err(Clone::clone)
+    }
+}
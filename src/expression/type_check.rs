@@ -0,0 +1,848 @@
+use std::collections::BTreeMap;
+
+use crate::{expression_discriminant, ExpressionNode, QueryError, SchemaNode, Value};
+
+/// Schema inferred for an expression.
+///
+/// Unlike [`SchemaNode`] it can be partially unknown, an expression like `None`
+/// only tells that it is the first variant of a sum, not what the other
+/// variants are.
+#[derive(Clone, Debug)]
+enum Type {
+    Unknown,
+    Product(Vec<Type>),
+    /// Known variants of the sum by discriminant, and the number of variants
+    /// if it is known.
+    Sum(BTreeMap<u32, Type>, Option<u32>),
+    List(Box<Type>),
+    Map(Box<(Type, Type)>),
+    Set(Box<Type>),
+    /// Any schema without children.
+    Terminal(SchemaNode),
+}
+
+impl Type {
+    fn from_schema(schema: &SchemaNode) -> Self {
+        match schema {
+            SchemaNode::Product(fields) => {
+                Self::Product(fields.iter().map(Self::from_schema).collect())
+            }
+            SchemaNode::Sum(variants) => Self::Sum(
+                (0..).zip(variants.iter().map(Self::from_schema)).collect(),
+                Some(variants.len().try_into().unwrap()),
+            ),
+            SchemaNode::List(element) => Self::List(Box::new(Self::from_schema(element))),
+            SchemaNode::Map(entry) => Self::Map(Box::new((
+                Self::from_schema(&entry.0),
+                Self::from_schema(&entry.1),
+            ))),
+            SchemaNode::Set(element) => Self::Set(Box::new(Self::from_schema(element))),
+            SchemaNode::String
+            | SchemaNode::Boolean
+            | SchemaNode::Unit
+            | SchemaNode::Uint8
+            | SchemaNode::Uint16
+            | SchemaNode::Uint32
+            | SchemaNode::Uint64
+            | SchemaNode::Uint128
+            | SchemaNode::Int8
+            | SchemaNode::Int16
+            | SchemaNode::Int32
+            | SchemaNode::Int64
+            | SchemaNode::Int128
+            | SchemaNode::Float32
+            | SchemaNode::Float64 => Self::Terminal(schema.clone()),
+        }
+    }
+
+    /// Most precise type compatible with both `self` and `other`, `None` if
+    /// they are incompatible.
+    fn unify(self, other: Self) -> Option<Self> {
+        Some(match (self, other) {
+            (Self::Unknown, other) | (other, Self::Unknown) => other,
+            (Self::Product(lhs), Self::Product(rhs)) => {
+                if lhs.len() != rhs.len() {
+                    return None;
+                }
+
+                Self::Product(
+                    lhs.into_iter()
+                        .zip(rhs)
+                        .map(|(lhs, rhs)| lhs.unify(rhs))
+                        .collect::<Option<_>>()?,
+                )
+            }
+            (Self::Sum(mut lhs, lhs_count), Self::Sum(rhs, rhs_count)) => {
+                let count = match (lhs_count, rhs_count) {
+                    (Some(lhs_count), Some(rhs_count)) if lhs_count != rhs_count => return None,
+                    (count, None) | (None, count) | (count, Some(_)) => count,
+                };
+
+                for (discriminant, variant) in rhs {
+                    let variant = match lhs.remove(&discriminant) {
+                        Some(other) => other.unify(variant)?,
+                        None => variant,
+                    };
+
+                    lhs.insert(discriminant, variant);
+                }
+
+                if count.is_some_and(|count| lhs.keys().any(|discriminant| *discriminant >= count))
+                {
+                    return None;
+                }
+
+                Self::Sum(lhs, count)
+            }
+            (Self::List(lhs), Self::List(rhs)) => Self::List(Box::new(lhs.unify(*rhs)?)),
+            (Self::Set(lhs), Self::Set(rhs)) => Self::Set(Box::new(lhs.unify(*rhs)?)),
+            (Self::Map(lhs), Self::Map(rhs)) => {
+                let (lhs_key, lhs_value) = *lhs;
+                let (rhs_key, rhs_value) = *rhs;
+
+                Self::Map(Box::new((
+                    lhs_key.unify(rhs_key)?,
+                    lhs_value.unify(rhs_value)?,
+                )))
+            }
+            (Self::Terminal(lhs), Self::Terminal(rhs)) => {
+                if lhs != rhs {
+                    return None;
+                }
+
+                Self::Terminal(lhs)
+            }
+            _ => return None,
+        })
+    }
+
+    fn option(element: Self) -> Self {
+        Self::Sum(
+            BTreeMap::from([(0, Self::Terminal(SchemaNode::Unit)), (1, element)]),
+            Some(2),
+        )
+    }
+
+    /// Type of a [`SlotMap`](crate::SlotMap) of `element`.
+    fn slot_map(element: Self) -> Self {
+        Self::Product(vec![
+            Self::List(Box::new(Self::Product(vec![
+                Self::Terminal(SchemaNode::Uint32),
+                Self::option(element),
+            ]))),
+            Self::List(Box::new(Self::Terminal(SchemaNode::Uint32))),
+        ])
+    }
+
+    /// Type of the elements of a type unified with [`Type::slot_map`].
+    fn slot_map_element(self) -> Self {
+        let Self::Product(mut fields) = self else {
+            return Self::Unknown;
+        };
+        let Self::List(slot) = fields.swap_remove(0) else {
+            return Self::Unknown;
+        };
+        let Self::Product(mut slot) = *slot else {
+            return Self::Unknown;
+        };
+        let Self::Sum(mut variants, _) = slot.swap_remove(1) else {
+            return Self::Unknown;
+        };
+
+        variants.remove(&1).unwrap_or(Self::Unknown)
+    }
+
+    fn expect(self, expected: Self, expression: u8) -> Result<Self, QueryError> {
+        self.unify(expected)
+            .ok_or(QueryError::InvalidOperand(expression))
+    }
+
+    fn expect_numeric(self, expression: u8) -> Result<Self, QueryError> {
+        match self {
+            Self::Terminal(
+                SchemaNode::Uint8
+                | SchemaNode::Uint16
+                | SchemaNode::Uint32
+                | SchemaNode::Uint64
+                | SchemaNode::Uint128
+                | SchemaNode::Int8
+                | SchemaNode::Int16
+                | SchemaNode::Int32
+                | SchemaNode::Int64
+                | SchemaNode::Int128
+                | SchemaNode::Float32
+                | SchemaNode::Float64,
+            )
+            | Self::Unknown => Ok(self),
+            _ => Err(QueryError::InvalidOperand(expression)),
+        }
+    }
+
+    /// Types of the keys and values of a map.
+    fn expect_map(self, expression: u8) -> Result<(Self, Self), QueryError> {
+        match self {
+            Self::Map(entry) => Ok(*entry),
+            Self::Unknown => Ok((Self::Unknown, Self::Unknown)),
+            _ => Err(QueryError::InvalidOperand(expression)),
+        }
+    }
+
+    fn expect_set(self, expression: u8) -> Result<Self, QueryError> {
+        match self {
+            Self::Set(element) => Ok(*element),
+            Self::Unknown => Ok(Self::Unknown),
+            _ => Err(QueryError::InvalidOperand(expression)),
+        }
+    }
+
+    fn expect_list(self, expression: u8) -> Result<Self, QueryError> {
+        match self {
+            Self::List(element) => Ok(*element),
+            Self::Unknown => Ok(Self::Unknown),
+            _ => Err(QueryError::InvalidOperand(expression)),
+        }
+    }
+}
+
+impl ExpressionNode {
+    /// Check that this expression is well typed when evaluated on a database
+    /// with the given `schema`.
+    ///
+    /// Once checked, evaluating the expression can only fail for reasons that
+    /// depend on the values, like an index out of bounds, and never writes a
+    /// value of the wrong shape into the database.
+    pub fn type_check(&self, schema: &SchemaNode) -> Result<(), QueryError> {
+        self.infer(&[Type::from_schema(schema)]).map(|_| ())
+    }
+
+    fn infer(&self, scopes: &[Type]) -> Result<Type, QueryError> {
+        let boolean = || Type::Terminal(SchemaNode::Boolean);
+        let uint32 = || Type::Terminal(SchemaNode::Uint32);
+        let string = || Type::Terminal(SchemaNode::String);
+        let with_scope = |scope: Type| scopes.iter().cloned().chain([scope]).collect::<Vec<_>>();
+
+        Ok(match self {
+            ExpressionNode::Path(path) => {
+                let invalid_path = || QueryError::InvalidPath(path.clone());
+
+                let Some((scope, segments)) = path.split_first() else {
+                    return Err(invalid_path());
+                };
+
+                let mut current = scopes
+                    .get(*scope as usize)
+                    .ok_or_else(invalid_path)?
+                    .clone();
+
+                for segment in segments {
+                    current = match current {
+                        Type::Unknown => Type::Unknown,
+                        Type::Product(mut fields) => {
+                            if *segment as usize >= fields.len() {
+                                return Err(invalid_path());
+                            }
+
+                            fields.swap_remove(*segment as usize)
+                        }
+                        Type::Sum(mut variants, count) => {
+                            if count.is_some_and(|count| *segment >= count) {
+                                return Err(invalid_path());
+                            }
+
+                            variants.remove(segment).unwrap_or(Type::Unknown)
+                        }
+                        Type::List(element) => *element,
+                        Type::Map(_) | Type::Set(_) | Type::Terminal(_) => {
+                            return Err(invalid_path())
+                        }
+                    };
+                }
+
+                current
+            }
+            ExpressionNode::Value(schema, _) => Type::from_schema(schema),
+            ExpressionNode::Set(operands) => {
+                let lhs = operands.0.infer(scopes)?;
+                let rhs = operands.1.infer(scopes)?;
+
+                lhs.expect(rhs, expression_discriminant::SET)?
+            }
+            ExpressionNode::Equal(operands) | ExpressionNode::NotEqual(operands) => {
+                let lhs = operands.0.infer(scopes)?;
+                let rhs = operands.1.infer(scopes)?;
+
+                lhs.expect(rhs, self.discriminant())?;
+
+                boolean()
+            }
+            ExpressionNode::Filter(operands) => {
+                let list = operands.0.infer(scopes)?;
+                let element = match list.clone() {
+                    Type::Set(element) => *element,
+                    list => list.expect_list(expression_discriminant::FILTER)?,
+                };
+
+                operands
+                    .1
+                    .infer(&with_scope(element))?
+                    .expect(boolean(), expression_discriminant::FILTER)?;
+
+                list
+            }
+            ExpressionNode::Map(operands) => {
+                let element = operands
+                    .0
+                    .infer(scopes)?
+                    .expect_list(expression_discriminant::MAP)?;
+
+                Type::List(Box::new(operands.1.infer(&with_scope(element))?))
+            }
+            ExpressionNode::Length(operand) => {
+                operand
+                    .infer(scopes)?
+                    .expect_list(expression_discriminant::LENGTH)?;
+
+                uint32()
+            }
+            ExpressionNode::Insert(operands) => {
+                let list = operands.0.infer(scopes)?;
+                let element = list.clone().expect_list(expression_discriminant::INSERT)?;
+
+                operands
+                    .1
+                    .infer(scopes)?
+                    .expect(uint32(), expression_discriminant::INSERT)?;
+                let element = operands
+                    .2
+                    .infer(scopes)?
+                    .expect(element, expression_discriminant::INSERT)?;
+
+                list.expect(
+                    Type::List(Box::new(element)),
+                    expression_discriminant::INSERT,
+                )?
+            }
+            ExpressionNode::Push(operands) => {
+                let list = operands.0.infer(scopes)?;
+                let element = list.expect_list(expression_discriminant::PUSH)?;
+
+                operands
+                    .1
+                    .infer(scopes)?
+                    .expect(element, expression_discriminant::PUSH)?;
+
+                uint32()
+            }
+            ExpressionNode::Pop(operand) => {
+                let element = operand
+                    .infer(scopes)?
+                    .expect_list(expression_discriminant::POP)?;
+
+                Type::option(element)
+            }
+            ExpressionNode::Remove(operands) => {
+                operands
+                    .1
+                    .infer(scopes)?
+                    .expect(uint32(), expression_discriminant::REMOVE)?;
+
+                operands
+                    .0
+                    .infer(scopes)?
+                    .expect_list(expression_discriminant::REMOVE)?
+            }
+            ExpressionNode::Clear(operand) => {
+                let list = operand.infer(scopes)?;
+                list.clone().expect_list(expression_discriminant::CLEAR)?;
+
+                list
+            }
+            ExpressionNode::Truncate(operands) => {
+                operands
+                    .1
+                    .infer(scopes)?
+                    .expect(uint32(), expression_discriminant::TRUNCATE)?;
+
+                let list = operands.0.infer(scopes)?;
+                list.clone()
+                    .expect_list(expression_discriminant::TRUNCATE)?;
+
+                list
+            }
+            ExpressionNode::Extend(operands) => {
+                let list = operands.0.infer(scopes)?;
+                list.clone().expect_list(expression_discriminant::EXTEND)?;
+
+                operands
+                    .1
+                    .infer(scopes)?
+                    .expect(list, expression_discriminant::EXTEND)?;
+
+                uint32()
+            }
+            ExpressionNode::SlotMapInsert(operands) => {
+                let element = operands.1.infer(scopes)?;
+
+                operands.0.infer(scopes)?.expect(
+                    Type::slot_map(element),
+                    expression_discriminant::SLOT_MAP_INSERT,
+                )?;
+
+                Type::Product(vec![uint32(), uint32()])
+            }
+            ExpressionNode::SlotMapRemove(operands) => {
+                operands.1.infer(scopes)?.expect(
+                    Type::Product(vec![uint32(), uint32()]),
+                    expression_discriminant::SLOT_MAP_REMOVE,
+                )?;
+
+                let slot_map = operands.0.infer(scopes)?.expect(
+                    Type::slot_map(Type::Unknown),
+                    expression_discriminant::SLOT_MAP_REMOVE,
+                )?;
+
+                Type::option(slot_map.slot_map_element())
+            }
+            ExpressionNode::MapGet(operands)
+            | ExpressionNode::MapRemove(operands)
+            | ExpressionNode::MapContainsKey(operands) => {
+                let (key, value) = operands.0.infer(scopes)?.expect_map(self.discriminant())?;
+
+                operands.1.infer(scopes)?.expect(key, self.discriminant())?;
+
+                match self {
+                    ExpressionNode::MapContainsKey(_) => boolean(),
+                    _ => Type::option(value),
+                }
+            }
+            ExpressionNode::MapInsert(operands) => {
+                let map = Type::Map(Box::new((
+                    operands.1.infer(scopes)?,
+                    operands.2.infer(scopes)?,
+                )));
+
+                let (_, value) = operands
+                    .0
+                    .infer(scopes)?
+                    .expect(map, expression_discriminant::MAP_INSERT)?
+                    .expect_map(expression_discriminant::MAP_INSERT)?;
+
+                Type::option(value)
+            }
+            ExpressionNode::SetContains(operands)
+            | ExpressionNode::SetInsert(operands)
+            | ExpressionNode::SetRemove(operands) => {
+                let element = operands.0.infer(scopes)?.expect_set(self.discriminant())?;
+
+                operands
+                    .1
+                    .infer(scopes)?
+                    .expect(element, self.discriminant())?;
+
+                boolean()
+            }
+            ExpressionNode::SetUnion(operands)
+            | ExpressionNode::SetIntersection(operands)
+            | ExpressionNode::SetDifference(operands) => {
+                let set = operands.0.infer(scopes)?;
+                set.clone().expect_set(self.discriminant())?;
+
+                operands.1.infer(scopes)?.expect(set, self.discriminant())?
+            }
+            ExpressionNode::Concat(operands)
+            | ExpressionNode::StringContains(operands)
+            | ExpressionNode::StartsWith(operands)
+            | ExpressionNode::EndsWith(operands) => {
+                operands
+                    .0
+                    .infer(scopes)?
+                    .expect(string(), self.discriminant())?;
+                operands
+                    .1
+                    .infer(scopes)?
+                    .expect(string(), self.discriminant())?;
+
+                match self {
+                    ExpressionNode::Concat(_) => string(),
+                    _ => boolean(),
+                }
+            }
+            ExpressionNode::Matches(operands) => {
+                operands.1.regex()?;
+
+                operands
+                    .0
+                    .infer(scopes)?
+                    .expect(string(), expression_discriminant::MATCHES)?;
+
+                boolean()
+            }
+            ExpressionNode::ListSum(operands) => {
+                let element = operands
+                    .0
+                    .infer(scopes)?
+                    .expect_list(expression_discriminant::LIST_SUM)?
+                    .expect_numeric(expression_discriminant::LIST_SUM)?;
+
+                element
+                    .expect(
+                        Type::from_schema(&operands.1),
+                        expression_discriminant::LIST_SUM,
+                    )?
+                    .expect_numeric(expression_discriminant::LIST_SUM)?
+            }
+            ExpressionNode::Min(operand) | ExpressionNode::Max(operand) => {
+                Type::option(operand.infer(scopes)?.expect_list(self.discriminant())?)
+            }
+            ExpressionNode::Average(operand) => {
+                operand
+                    .infer(scopes)?
+                    .expect_list(expression_discriminant::AVERAGE)?
+                    .expect_numeric(expression_discriminant::AVERAGE)?;
+
+                Type::option(Type::Terminal(SchemaNode::Float64))
+            }
+            ExpressionNode::MinBy(operands) | ExpressionNode::MaxBy(operands) => {
+                let element = operands.0.infer(scopes)?.expect_list(self.discriminant())?;
+
+                operands.1.infer(&with_scope(element.clone()))?;
+
+                Type::option(element)
+            }
+            ExpressionNode::Count(operands) => {
+                let element = operands
+                    .0
+                    .infer(scopes)?
+                    .expect_list(expression_discriminant::COUNT)?;
+
+                operands
+                    .1
+                    .infer(&with_scope(element))?
+                    .expect(boolean(), expression_discriminant::COUNT)?;
+
+                uint32()
+            }
+            ExpressionNode::Sorted(operands) | ExpressionNode::Sort(operands) => {
+                let list = operands.0.infer(scopes)?;
+                let element = list.clone().expect_list(self.discriminant())?;
+
+                for (key, _) in &operands.1 {
+                    key.infer(&with_scope(element.clone()))?;
+                }
+
+                match self {
+                    ExpressionNode::Sorted(_) => list,
+                    _ => Type::Terminal(SchemaNode::Unit),
+                }
+            }
+            ExpressionNode::Skip(operands) | ExpressionNode::Take(operands) => {
+                operands
+                    .1
+                    .infer(scopes)?
+                    .expect(uint32(), self.discriminant())?;
+
+                let list = operands.0.infer(scopes)?;
+                list.clone().expect_list(self.discriminant())?;
+
+                list
+            }
+            ExpressionNode::Join(operands) => {
+                let element = operands
+                    .0
+                    .infer(scopes)?
+                    .expect_list(expression_discriminant::JOIN)?;
+
+                operands.1.infer(&with_scope(element.clone()))?.expect(
+                    Type::Product(vec![uint32(), uint32()]),
+                    expression_discriminant::JOIN,
+                )?;
+
+                let slot_map = operands
+                    .2
+                    .infer(scopes)?
+                    .expect(Type::slot_map(Type::Unknown), expression_discriminant::JOIN)?;
+
+                Type::List(Box::new(Type::Product(vec![
+                    element,
+                    Type::option(slot_map.slot_map_element()),
+                ])))
+            }
+            ExpressionNode::GroupBy(operands) => {
+                let element = operands
+                    .0
+                    .infer(scopes)?
+                    .expect_list(expression_discriminant::GROUP_BY)?;
+
+                let key = operands.1.infer(&with_scope(element.clone()))?;
+
+                Type::Map(Box::new((key, Type::List(Box::new(element)))))
+            }
+            ExpressionNode::TransformValues(operands) => {
+                let (key, value) = operands
+                    .0
+                    .infer(scopes)?
+                    .expect_map(expression_discriminant::TRANSFORM_VALUES)?;
+
+                Type::Map(Box::new((key, operands.1.infer(&with_scope(value))?)))
+            }
+            ExpressionNode::SlotMapPage(operands) => {
+                let key = Type::Product(vec![uint32(), uint32()]);
+
+                operands.1.infer(scopes)?.expect(
+                    Type::option(key.clone()),
+                    expression_discriminant::SLOT_MAP_PAGE,
+                )?;
+                operands
+                    .2
+                    .infer(scopes)?
+                    .expect(uint32(), expression_discriminant::SLOT_MAP_PAGE)?;
+
+                let slot_map = operands.0.infer(scopes)?.expect(
+                    Type::slot_map(Type::Unknown),
+                    expression_discriminant::SLOT_MAP_PAGE,
+                )?;
+
+                Type::List(Box::new(Type::Product(vec![
+                    key,
+                    slot_map.slot_map_element(),
+                ])))
+            }
+            ExpressionNode::Reversed(operand) => {
+                let list = operand.infer(scopes)?;
+                list.clone()
+                    .expect_list(expression_discriminant::REVERSED)?;
+
+                list
+            }
+            ExpressionNode::Reverse(operand) => {
+                operand
+                    .infer(scopes)?
+                    .expect_list(expression_discriminant::REVERSE)?;
+
+                Type::Terminal(SchemaNode::Unit)
+            }
+            ExpressionNode::StringLength(operand) => {
+                operand
+                    .infer(scopes)?
+                    .expect(string(), expression_discriminant::STRING_LENGTH)?;
+
+                uint32()
+            }
+            ExpressionNode::Substring(operands) => {
+                operands
+                    .1
+                    .infer(scopes)?
+                    .expect(uint32(), expression_discriminant::SUBSTRING)?;
+                operands
+                    .2
+                    .infer(scopes)?
+                    .expect(uint32(), expression_discriminant::SUBSTRING)?;
+
+                operands
+                    .0
+                    .infer(scopes)?
+                    .expect(string(), expression_discriminant::SUBSTRING)?
+            }
+            ExpressionNode::ToLowercase(operand) | ExpressionNode::ToUppercase(operand) => operand
+                .infer(scopes)?
+                .expect(string(), self.discriminant())?,
+            ExpressionNode::MapKeys(operand)
+            | ExpressionNode::MapValues(operand)
+            | ExpressionNode::MapEntries(operand) => {
+                let (key, value) = operand.infer(scopes)?.expect_map(self.discriminant())?;
+
+                Type::List(Box::new(match self {
+                    ExpressionNode::MapKeys(_) => key,
+                    ExpressionNode::MapValues(_) => value,
+                    _ => Type::Product(vec![key, value]),
+                }))
+            }
+            ExpressionNode::And(operands)
+            | ExpressionNode::Or(operands)
+            | ExpressionNode::Xor(operands) => {
+                operands
+                    .0
+                    .infer(scopes)?
+                    .expect(boolean(), self.discriminant())?;
+                operands
+                    .1
+                    .infer(scopes)?
+                    .expect(boolean(), self.discriminant())?;
+
+                boolean()
+            }
+            ExpressionNode::Not(operand) => operand
+                .infer(scopes)?
+                .expect(boolean(), expression_discriminant::NOT)?,
+            ExpressionNode::MapVariant(operands) => {
+                let (sum, discriminant, map) = operands.as_ref();
+
+                let (mut variants, count) = match sum.infer(scopes)? {
+                    Type::Sum(variants, count) => (variants, count),
+                    Type::Unknown => (BTreeMap::new(), None),
+                    _ => {
+                        return Err(QueryError::InvalidOperand(
+                            expression_discriminant::MAP_VARIANT,
+                        ))
+                    }
+                };
+
+                if count.is_some_and(|count| *discriminant >= count) {
+                    return Err(QueryError::InvalidOperand(
+                        expression_discriminant::MAP_VARIANT,
+                    ));
+                }
+
+                let variant = variants.remove(discriminant).unwrap_or(Type::Unknown);
+                variants.insert(*discriminant, map.infer(&with_scope(variant))?);
+
+                Type::Sum(variants, count)
+            }
+            ExpressionNode::Let(operands) => {
+                let value = operands.0.infer(scopes)?;
+
+                operands.1.infer(&with_scope(value))?
+            }
+            ExpressionNode::IsVariant(operands) => {
+                let (sum, discriminant) = operands.as_ref();
+
+                match sum.infer(scopes)? {
+                    Type::Sum(_, count) if count.is_none_or(|count| *discriminant < count) => {}
+                    Type::Unknown => {}
+                    _ => {
+                        return Err(QueryError::InvalidOperand(
+                            expression_discriminant::IS_VARIANT,
+                        ))
+                    }
+                }
+
+                boolean()
+            }
+            ExpressionNode::Match(operands) => {
+                let (sum, arms) = operands.as_ref();
+                let invalid_operand = || QueryError::InvalidOperand(expression_discriminant::MATCH);
+
+                let mut variants = match sum.infer(scopes)? {
+                    Type::Sum(variants, count) => {
+                        if count.is_some_and(|count| count as usize != arms.len()) {
+                            return Err(invalid_operand());
+                        }
+
+                        variants
+                    }
+                    Type::Unknown => BTreeMap::new(),
+                    _ => return Err(invalid_operand()),
+                };
+
+                // Variants without an arm can't be matched.
+                if variants
+                    .keys()
+                    .any(|discriminant| *discriminant as usize >= arms.len())
+                {
+                    return Err(invalid_operand());
+                }
+
+                arms.iter()
+                    .zip(0..)
+                    .try_fold(Type::Unknown, |matched, (arm, discriminant)| {
+                        let variant = variants.remove(&discriminant).unwrap_or(Type::Unknown);
+
+                        matched
+                            .unify(arm.infer(&with_scope(variant))?)
+                            .ok_or_else(invalid_operand)
+                    })?
+            }
+            ExpressionNode::Fuse(operand) => match operand.infer(scopes)? {
+                Type::Sum(variants, _) => variants
+                    .into_values()
+                    .try_fold(Type::Unknown, |fused, variant| fused.unify(variant))
+                    .ok_or(QueryError::InvalidOperand(expression_discriminant::FUSE))?,
+                Type::Unknown => Type::Unknown,
+                _ => return Err(QueryError::InvalidOperand(expression_discriminant::FUSE)),
+            },
+            ExpressionNode::Chain(operands) => {
+                operands.0.infer(scopes)?;
+                operands.1.infer(scopes)?
+            }
+            ExpressionNode::Get(operands) => {
+                let (value, index) = operands.as_ref();
+
+                index
+                    .infer(scopes)?
+                    .expect(uint32(), expression_discriminant::GET)?;
+
+                match value.infer(scopes)? {
+                    Type::Product(fields) => match index {
+                        // The type of the field can only be known for a constant index.
+                        ExpressionNode::Value(_, Value::Uint32(index)) => fields
+                            .into_iter()
+                            .nth(*index as usize)
+                            .ok_or(QueryError::InvalidOperand(expression_discriminant::GET))?,
+                        _ => fields
+                            .into_iter()
+                            .try_fold(Type::Unknown, |field, other| field.unify(other))
+                            .ok_or(QueryError::InvalidOperand(expression_discriminant::GET))?,
+                    },
+                    Type::List(element) => Type::option(*element),
+                    Type::Unknown => Type::Unknown,
+                    _ => return Err(QueryError::InvalidOperand(expression_discriminant::GET)),
+                }
+            }
+            ExpressionNode::Condition(operands) => {
+                operands
+                    .0
+                    .infer(scopes)?
+                    .expect(boolean(), expression_discriminant::CONDITION)?;
+
+                let if_branch = operands.1.infer(scopes)?;
+                let else_branch = operands.2.infer(scopes)?;
+
+                if_branch.expect(else_branch, expression_discriminant::CONDITION)?
+            }
+            ExpressionNode::Product(fields) => Type::Product(
+                fields
+                    .iter()
+                    .map(|field| field.infer(scopes))
+                    .collect::<Result<_, _>>()?,
+            ),
+            ExpressionNode::Sum(operands) => {
+                let (discriminant, variant) = operands.as_ref();
+
+                Type::Sum(
+                    BTreeMap::from([(*discriminant, variant.infer(scopes)?)]),
+                    None,
+                )
+            }
+            ExpressionNode::List(elements) => Type::List(Box::new(elements.iter().try_fold(
+                Type::Unknown,
+                |element, other| {
+                    element
+                        .unify(other.infer(scopes)?)
+                        .ok_or(QueryError::InvalidOperand(expression_discriminant::LIST))
+                },
+            )?)),
+            ExpressionNode::Add(operands)
+            | ExpressionNode::Sub(operands)
+            | ExpressionNode::Mul(operands)
+            | ExpressionNode::Div(operands)
+            | ExpressionNode::Rem(operands) => {
+                let lhs = operands.0.infer(scopes)?;
+                let rhs = operands.1.infer(scopes)?;
+
+                lhs.expect(rhs, self.discriminant())?
+                    .expect_numeric(self.discriminant())?
+            }
+            ExpressionNode::Less(operands)
+            | ExpressionNode::LessEqual(operands)
+            | ExpressionNode::Greater(operands)
+            | ExpressionNode::GreaterEqual(operands) => {
+                let lhs = operands.0.infer(scopes)?;
+                let rhs = operands.1.infer(scopes)?;
+
+                lhs.expect(rhs, self.discriminant())?;
+
+                boolean()
+            }
+        })
+    }
+}
@@ -8,7 +8,7 @@
 //! Simple database storing an array of users
 //!
 //! ```
-//! # use database::{Schema, Client, Server, SchemaNode, Value, Filter, StringEqual};
+//! # use database::{Schema, Client, Server, SchemaNode, Value, VecFilter, Equal};
 //! #
 //! #[derive(Schema, Debug, PartialEq)]
 //! enum Shape {
@@ -42,7 +42,8 @@
 //!             let mut client = Client::<(), _>::new(client_stream)
 //!                 .await?
 //!                 // The `set` method actually reset the whole database including schema.
-//!                 // We use it here because the database cannot yet be save on disk.
+//!                 // We use it here to start from a known state, see `Server::open` to
+//!                 // load a database saved on disk instead.
 //!                 .set(vec![
 //!                     User {
 //!                         name: "some user 1".to_string(),
@@ -92,7 +93,9 @@
 //! with the mongodb protocol as an example.
 //!
 //! A [`Server`] can hold multiple communications at a time. (Each call to
-//! [`Server::listen`] open a new communication).
+//! [`Server::listen`] open a new communication). Requests of different
+//! communications are executed one at a time, so each request sees the
+//! database either before or after the whole execution of another request.
 //!
 //! The protocol is driven by the [`Client`] with a request/response scheme.
 //!
@@ -103,17 +106,19 @@
 //!
 //! There a three kind of requests:
 //! - get schema:
-//!     The request does not take any payload.
+//!   The request does not take any payload.
 //!
-//!     The request directly respond with the [`Schema`] of the database.
+//!   The request directly respond with the [`Schema`] of the database.
 //! - set:
-//!     The request take the new [`Schema`] then [`Value`] of the database.
+//!   The request take the new [`Schema`] then [`Value`] of the database.
 //!
-//!     The request does not respond anything.
+//!   The request does not respond anything.
 //! - query:
-//!     The request take an [`Expression`] as payload.
+//!   The request take an [`Expression`] as payload.
 //!
-//!     The request returns a [`Value`], the [`Schema`] of this value depends on the [`Expression`].
+//!   The request respond with a byte discriminant, see [`response_discriminant`].
+//!   It is followed either by a [`Value`] whose [`Schema`] depends on the
+//!   [`Expression`], or by a [`QueryError`] if the evaluation failed.
 //!
 //! There are three kind of data that can be sent both ways in the protocol:
 //!
@@ -134,41 +139,70 @@
 //! TODO
 
 mod client;
+mod error;
 mod expression;
 mod schema;
 mod scope;
 mod server;
+mod storage;
 mod value;
 
 pub use crate::{
     client::Client,
+    error::{query_error_discriminant, Error, QueryError},
     expression::{
-        expression_discriminant, And, AndExpression, BoolOperators, Chain, ChainExpression,
-        ConditionExpression, EqualExpression, Expression, ExpressionNode, FilterExpression,
-        FlattenOperator, FromPath, FuseExpression, GetExpression, HashSetFilter, InsertExpression,
-        Int128Equal, Int16Equal, Int32Equal, Int64Equal, Int8Equal, Length, LengthExpression,
-        MapExpression, MapVariantExpression, MapVec, NonZeroInt128Equal, NonZeroInt16Equal,
-        NonZeroInt32Equal, NonZeroInt64Equal, NonZeroInt8Equal, NonZeroUint128Equal,
-        NonZeroUint16Equal, NonZeroUint32Equal, NonZeroUint64Equal, NonZeroUint8Equal,
-        OptionOperators, PathExpression, Set, SetExpression, SetIfSome, SlotMapOperators,
-        StringEqual, TupleExpression1, TupleExpression10, TupleExpression11, TupleExpression12,
-        TupleExpression13, TupleExpression14, TupleExpression15, TupleExpression16,
-        TupleExpression2, TupleExpression3, TupleExpression4, TupleExpression5, TupleExpression6,
-        TupleExpression7, TupleExpression8, TupleExpression9, Uint128Equal, Uint16Equal,
-        Uint32Equal, Uint64Equal, Uint8Equal, VecFilter, VecGet, VecInsert,
+        expression_discriminant, let_in, pattern_discriminant, AddExpression, Aggregate, And,
+        AndExpression, Arithmetic, Ascending, AverageExpression, BoolOperators, Chain,
+        ChainExpression, ClearExpression, Compare, CompiledPattern, ConcatExpression,
+        ConditionExpression, CountExpression, Descending, DivExpression, EndsWithExpression, Equal,
+        EqualExpression, Expression, ExpressionNode, ExtendExpression, FilterExpression,
+        FlattenOperator, FromPath, FuseExpression, GetExpression, GreaterEqualExpression,
+        GreaterExpression, GroupBy, GroupByExpression, HashMapOperators, HashSetFilter,
+        HashSetOperators, InsertExpression, IsVariantExpression, Join, JoinExpression, Length,
+        LengthExpression, LessEqualExpression, LessExpression, LetExpression, ListSumExpression,
+        MapContainsKeyExpression, MapEntriesExpression, MapExpression, MapGetExpression,
+        MapInsertExpression, MapKeysExpression, MapRemoveExpression, MapValuesExpression,
+        MapVariantExpression, MapVec, MatchArms, MatchExpression, MatchesExpression,
+        MaxByExpression, MaxExpression, MinByExpression, MinExpression, MulExpression,
+        NotEqualExpression, NotExpression, Numeric, OptionOperators, Or, OrExpression, Ordered,
+        Paginate, PathExpression, Pattern, PopExpression, PushExpression, RemExpression,
+        RemoveExpression, ReverseExpression, ReversedExpression, Set, SetContainsExpression,
+        SetDifferenceExpression, SetExpression, SetIfSome, SetInsertExpression,
+        SetIntersectionExpression, SetRemoveExpression, SetUnionExpression, SkipExpression,
+        SlotMapInsertExpression, SlotMapOperators, SlotMapPageExpression, SlotMapRemoveExpression,
+        Sort, SortDirection, SortExpression, SortKey, SortedExpression, StartsWithExpression,
+        StringContainsExpression, StringLengthExpression, StringOperators, SubExpression,
+        SubstringExpression, TakeExpression, ToLowercaseExpression, ToUppercaseExpression,
+        TransformValuesExpression, TruncateExpression, TupleExpression1, TupleExpression10,
+        TupleExpression11, TupleExpression12, TupleExpression13, TupleExpression14,
+        TupleExpression15, TupleExpression16, TupleExpression2, TupleExpression3, TupleExpression4,
+        TupleExpression5, TupleExpression6, TupleExpression7, TupleExpression8, TupleExpression9,
+        VariantExpression, VariantFields, VecFilter, VecGet, VecInsert, VecOperators, Xor,
+        XorExpression,
     },
     schema::{schema_discriminant, DefaultKey, Key, OptionMapped, Schema, SchemaNode, SlotMap},
-    server::{request_discriminant, Server},
-    value::Value,
+    server::{request_discriminant, response_discriminant, Server},
+    value::{Value, ValueMap, ValueSet},
 };
 
 pub use database_derive::Schema;
 
-pub(crate) use crate::scope::Scope;
+use std::{
+    future::Future,
+    pin::pin,
+    task::{Context, Poll, Waker},
+};
+
+pub(crate) use crate::{
+    scope::Scope,
+    storage::{LogEntry, Storage},
+};
 
 #[doc(hidden)]
 pub mod __internal {
     pub use tokio;
+
+    pub use crate::expression::{is_variant, match_variants, variant, variant_arm};
 }
 
 macro_rules! io_error {
@@ -181,3 +215,14 @@ macro_rules! io_error {
 }
 
 pub(crate) use io_error;
+
+/// Drive a future that only writes into memory to completion.
+///
+/// Writing into a `Vec<u8>` is always ready so such a future never yields, this
+/// allows serializing values without holding their locks across an await point.
+pub(crate) fn complete<F: Future>(future: F) -> F::Output {
+    match pin!(future).poll(&mut Context::from_waker(Waker::noop())) {
+        Poll::Ready(output) => output,
+        Poll::Pending => unreachable!("in memory write future yielded"),
+    }
+}
@@ -13,9 +13,7 @@ impl<K: Schema + Send + Sync + Eq + Hash, V: Schema + Send + Sync> Schema for Ha
         write: &mut (impl AsyncWriteExt + Unpin + Send),
     ) -> impl Future<Output = io::Result<()>> + Send {
         async {
-            write.write_u8(schema_discriminant::LIST).await?;
-            write.write_u8(schema_discriminant::PRODUCT).await?;
-            write.write_u32(2).await?;
+            write.write_u8(schema_discriminant::MAP).await?;
             K::write_schema(write).await?;
             V::write_schema(write).await?;
 
@@ -32,7 +30,7 @@ impl<K: Schema + Send + Sync + Eq + Hash, V: Schema + Send + Sync> Schema for Ha
                 .write_u32(self.len().try_into().map_err(|_| {
                     io_error!(
                         OutOfMemory,
-                        "list value length doesn't fit into a 32 bit unsigned integer",
+                        "map value length doesn't fit into a 32 bit unsigned integer",
                     )
                 })?)
                 .await?;
@@ -53,13 +51,13 @@ impl<K: Schema + Send + Sync + Eq + Hash, V: Schema + Send + Sync> Schema for Ha
             let length: usize = read.read_u32().await?.try_into().map_err(|_| {
                 io_error!(
                     OutOfMemory,
-                    "list value length doesn't fit into a pointer sized unsigned integer",
+                    "map value length doesn't fit into a pointer sized unsigned integer",
                 )
             })?;
 
             let mut values = HashMap::new();
             values.try_reserve(length).map_err(|_| {
-                io_error!(OutOfMemory, "allocation of memory for list values failed")
+                io_error!(OutOfMemory, "allocation of memory for map values failed")
             })?;
 
             for _ in 0..length {
@@ -76,7 +74,6 @@ impl<K: Schema + Send + Sync + Eq + Hash, V: Schema + Send + Sync> Schema for Ha
     }
 }
 
-// TODO: find a way to pass hashmap containing expressions in query
 impl<K: Schema + Send + Sync + Eq + Hash, V: Schema + Send + Sync> Expression for HashMap<K, V> {
     type Target = HashMap<K, V>;
 
@@ -85,24 +82,9 @@ impl<K: Schema + Send + Sync + Eq + Hash, V: Schema + Send + Sync> Expression fo
         write: &mut (impl AsyncWriteExt + Unpin + Send),
     ) -> impl Future<Output = io::Result<()>> {
         async move {
-            write.write_u8(expression_discriminant::LIST).await?;
-
-            write
-                .write_u32(self.len().try_into().map_err(|_| {
-                    io_error!(
-                        OutOfMemory,
-                        "list expression length doesn't fit into a 32 bit unsigned integer",
-                    )
-                })?)
-                .await?;
-
-            for (key, value) in self {
-                write.write_u8(expression_discriminant::VALUE).await?;
-                <(K, V)>::write_schema(write).await?;
-                (key, value).write_value(write).await?;
-            }
-
-            Ok(())
+            write.write_u8(expression_discriminant::VALUE).await?;
+            Self::write_schema(write).await?;
+            self.write_value(write).await
         }
     }
 }
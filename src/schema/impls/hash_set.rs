@@ -13,7 +13,7 @@ impl<T: Schema + Send + Sync + Eq + Hash> Schema for HashSet<T> {
         write: &mut (impl AsyncWriteExt + Unpin + Send),
     ) -> impl Future<Output = io::Result<()>> + Send {
         async {
-            write.write_u8(schema_discriminant::LIST).await?;
+            write.write_u8(schema_discriminant::SET).await?;
             T::write_schema(write).await?;
 
             Ok(())
@@ -29,7 +29,7 @@ impl<T: Schema + Send + Sync + Eq + Hash> Schema for HashSet<T> {
                 .write_u32(self.len().try_into().map_err(|_| {
                     io_error!(
                         OutOfMemory,
-                        "list value length doesn't fit into a 32 bit unsigned integer",
+                        "set value length doesn't fit into a 32 bit unsigned integer",
                     )
                 })?)
                 .await?;
@@ -49,13 +49,13 @@ impl<T: Schema + Send + Sync + Eq + Hash> Schema for HashSet<T> {
             let length: usize = read.read_u32().await?.try_into().map_err(|_| {
                 io_error!(
                     OutOfMemory,
-                    "list value length doesn't fit into a pointer sized unsigned integer",
+                    "set value length doesn't fit into a pointer sized unsigned integer",
                 )
             })?;
 
             let mut values = HashSet::new();
             values.try_reserve(length).map_err(|_| {
-                io_error!(OutOfMemory, "allocation of memory for list values failed")
+                io_error!(OutOfMemory, "allocation of memory for set values failed")
             })?;
 
             for _ in 0..length {
@@ -69,7 +69,6 @@ impl<T: Schema + Send + Sync + Eq + Hash> Schema for HashSet<T> {
     }
 }
 
-// TODO: find a way to pass hashset containing expressions in query
 impl<T: Schema + Send + Sync + Eq + Hash> Expression for HashSet<T> {
     type Target = HashSet<T>;
 
@@ -78,24 +77,9 @@ impl<T: Schema + Send + Sync + Eq + Hash> Expression for HashSet<T> {
         write: &mut (impl AsyncWriteExt + Unpin + Send),
     ) -> impl Future<Output = io::Result<()>> {
         async move {
-            write.write_u8(expression_discriminant::LIST).await?;
-
-            write
-                .write_u32(self.len().try_into().map_err(|_| {
-                    io_error!(
-                        OutOfMemory,
-                        "list expression length doesn't fit into a 32 bit unsigned integer",
-                    )
-                })?)
-                .await?;
-
-            for value in self {
-                write.write_u8(expression_discriminant::VALUE).await?;
-                T::write_schema(write).await?;
-                value.write_value(write).await?;
-            }
-
-            Ok(())
+            write.write_u8(expression_discriminant::VALUE).await?;
+            Self::write_schema(write).await?;
+            self.write_value(write).await
         }
     }
 }
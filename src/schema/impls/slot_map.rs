@@ -49,43 +49,49 @@ make_keys! {
     pub struct DefaultKey;
 }
 
+/// Map generating its own keys, in which a removed key never resolves again
+/// even if its slot is reused.
+///
+/// Each slot has a generation which is bumped when its value is removed, a key
+/// only resolves while the generation of its slot is the one it was created
+/// with. A slot whose generation can't be bumped anymore is never reused.
+///
+/// Free slots are kept in a list so inserting and removing are `O(1)`.
 #[derive(Debug)]
-pub struct SlotMap<K: Key, T>(Vec<(NonZeroU32, Option<T>)>, PhantomData<K>);
+pub struct SlotMap<K: Key, T> {
+    slots: Vec<(NonZeroU32, Option<T>)>,
+    free: Vec<u32>,
+    key: PhantomData<K>,
+}
 
 impl<K: Key, T> SlotMap<K, T> {
     pub fn new() -> Self {
-        Self(Vec::new(), PhantomData)
+        Self {
+            slots: Vec::new(),
+            free: Vec::new(),
+            key: PhantomData,
+        }
     }
 
     pub fn insert(&mut self, value: T) -> K {
-        // TODO: optimize by remembering the first available slots
-
-        if let Some((index, generation, slot)) = self
-            .0
-            .iter_mut()
-            .enumerate()
-            .filter_map(|(i, (generation, slot))| slot.is_none().then_some((i, generation, slot)))
-            .next()
-        {
-            *generation = generation
-                .checked_add(1)
-                .unwrap_or(NonZeroU32::new(1).unwrap());
+        if let Some(index) = self.free.pop() {
+            let (generation, slot) = &mut self.slots[index as usize];
 
             *slot = Some(value);
 
-            K::new(index as u32, *generation)
+            K::new(index, *generation)
         } else {
-            let index = self.0.len() as u32;
-            let generation = NonZeroU32::new(1).unwrap();
+            let index = self.slots.len() as u32;
+            let generation = NonZeroU32::MIN;
 
-            self.0.push((generation, Some(value)));
+            self.slots.push((generation, Some(value)));
 
             K::new(index, generation)
         }
     }
 
     pub fn get(&self, key: K) -> Option<&T> {
-        self.0
+        self.slots
             .get(usize::try_from(key.index()).ok()?)
             .and_then(|(generation, value)| {
                 (*generation == key.generation()).then_some(value.as_ref())
@@ -94,7 +100,7 @@ impl<K: Key, T> SlotMap<K, T> {
     }
 
     pub fn get_mut(&mut self, key: K) -> Option<&mut T> {
-        self.0
+        self.slots
             .get_mut(usize::try_from(key.index()).ok()?)
             .and_then(|(generation, value)| {
                 (*generation == key.generation()).then_some(value.as_mut())
@@ -103,20 +109,33 @@ impl<K: Key, T> SlotMap<K, T> {
     }
 
     pub fn remove(&mut self, key: K) -> Option<T> {
-        self.0
-            .get_mut(key.index() as usize)
-            .and_then(|(_, slot)| slot.take())
+        let (generation, slot) = self.slots.get_mut(usize::try_from(key.index()).ok()?)?;
+
+        if *generation != key.generation() {
+            return None;
+        }
+
+        let value = slot.take()?;
+
+        if let Some(next_generation) = generation.checked_add(1) {
+            *generation = next_generation;
+            self.free.push(key.index());
+        }
+
+        Some(value)
     }
 }
 
 impl<K: Key, T> FromIterator<T> for SlotMap<K, T> {
     fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
-        Self(
-            iter.into_iter()
-                .map(|value| (NonZeroU32::new(1).unwrap(), Some(value)))
+        Self {
+            slots: iter
+                .into_iter()
+                .map(|value| (NonZeroU32::MIN, Some(value)))
                 .collect(),
-            PhantomData,
-        )
+            free: Vec::new(),
+            key: PhantomData,
+        }
     }
 }
 
@@ -129,7 +148,7 @@ impl<K: Key, T> IntoIterator for SlotMap<K, T> {
     >;
 
     fn into_iter(self) -> Self::IntoIter {
-        self.0
+        self.slots
             .into_iter()
             .enumerate()
             .filter_map(|(index, (generation, value))| {
@@ -144,7 +163,7 @@ impl<K: Key + Send + Sync, T: Schema + Send + Sync> Schema for SlotMap<K, T> {
     fn write_schema(
         write: &mut (impl AsyncWriteExt + Unpin + Send),
     ) -> impl Future<Output = io::Result<()>> + Send {
-        Vec::<(u32, Option<T>)>::write_schema(write)
+        <(Vec<(u32, Option<T>)>, Vec<u32>)>::write_schema(write)
     }
 
     fn write_value(
@@ -153,7 +172,7 @@ impl<K: Key + Send + Sync, T: Schema + Send + Sync> Schema for SlotMap<K, T> {
     ) -> impl Future<Output = io::Result<()>> + Send {
         async move {
             write
-                .write_u32(self.0.len().try_into().map_err(|_| {
+                .write_u32(self.slots.len().try_into().map_err(|_| {
                     io_error!(
                         OutOfMemory,
                         "list value length doesn't fit into a 32 bit unsigned integer",
@@ -161,12 +180,12 @@ impl<K: Key + Send + Sync, T: Schema + Send + Sync> Schema for SlotMap<K, T> {
                 })?)
                 .await?;
 
-            for (generation, value) in &self.0 {
+            for (generation, value) in &self.slots {
                 generation.get().write_value(write).await?;
                 value.write_value(write).await?;
             }
 
-            Ok(())
+            self.free.write_value(write).await
         }
     }
 
@@ -194,12 +213,34 @@ impl<K: Key + Send + Sync, T: Schema + Send + Sync> Schema for SlotMap<K, T> {
                 values.push((generation, Option::<T>::read_value(read).await?));
             }
 
-            Ok(SlotMap(values, PhantomData))
+            let free = Vec::<u32>::read_value(read).await?;
+
+            let mut is_free = vec![false; values.len()];
+
+            for index in &free {
+                match (
+                    values.get(*index as usize),
+                    is_free.get_mut(*index as usize),
+                ) {
+                    (Some((_, None)), Some(is_free @ false)) => *is_free = true,
+                    _ => {
+                        return Err(io_error!(
+                            InvalidData,
+                            "free slot in slotmap doesn't exist, isn't empty or is repeated",
+                        ))
+                    }
+                }
+            }
+
+            Ok(SlotMap {
+                slots: values,
+                free,
+                key: PhantomData,
+            })
         }
     }
 }
 
-// TODO: find a way to pass hashmap containing expressions in query
 impl<K: Key + Send + Sync, T: Schema + Send + Sync> Expression for SlotMap<K, T> {
     type Target = SlotMap<K, T>;
 
@@ -208,27 +249,90 @@ impl<K: Key + Send + Sync, T: Schema + Send + Sync> Expression for SlotMap<K, T>
         write: &mut (impl AsyncWriteExt + Unpin + Send),
     ) -> impl Future<Output = io::Result<()>> {
         async move {
-            write.write_u8(expression_discriminant::LIST).await?;
+            write.write_u8(expression_discriminant::VALUE).await?;
+            Self::write_schema(write).await?;
+            self.write_value(write).await
+        }
+    }
+}
 
-            write
-                .write_u32(self.0.len().try_into().map_err(|_| {
-                    io_error!(
-                        OutOfMemory,
-                        "list expression length doesn't fit into a 32 bit unsigned integer",
-                    )
-                })?)
-                .await?;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{complete, DefaultKey};
 
-            for (generation, value) in self.0 {
-                write.write_u8(expression_discriminant::VALUE).await?;
+    fn copy(key: &DefaultKey) -> DefaultKey {
+        DefaultKey::new(key.index(), key.generation())
+    }
 
-                <(u32, Option<T>)>::write_schema(write).await?;
+    #[test]
+    fn removed_key_never_resolves_again() {
+        let mut slot_map = SlotMap::<DefaultKey, &str>::new();
 
-                generation.get().write_value(write).await?;
-                value.write_value(write).await?;
-            }
+        let removed = slot_map.insert("removed");
+        assert_eq!(slot_map.remove(copy(&removed)), Some("removed"));
+
+        let inserted = slot_map.insert("inserted");
+        assert_eq!(inserted.index(), removed.index());
+        assert_ne!(inserted.generation(), removed.generation());
+
+        assert_eq!(slot_map.get(copy(&removed)), None);
+        assert_eq!(slot_map.get_mut(copy(&removed)), None);
+        assert_eq!(slot_map.remove(copy(&removed)), None);
+        assert_eq!(slot_map.get(inserted), Some(&"inserted"));
+    }
+
+    #[test]
+    fn exhausted_slot_is_never_reused() {
+        let mut slot_map = SlotMap::<DefaultKey, &str> {
+            slots: vec![(NonZeroU32::MAX, Some("exhausted"))],
+            free: Vec::new(),
+            key: PhantomData,
+        };
+
+        let exhausted = DefaultKey::new(0, NonZeroU32::MAX);
+        assert_eq!(slot_map.remove(copy(&exhausted)), Some("exhausted"));
+        assert!(slot_map.free.is_empty());
+
+        let inserted = slot_map.insert("inserted");
+        assert_eq!(inserted.index(), 1);
+        assert_eq!(slot_map.get(exhausted), None);
+    }
 
-            Ok(())
+    #[test]
+    fn invalid_free_list_is_rejected() {
+        let read = |slots: Vec<(NonZeroU32, Option<u32>)>, free: Vec<u32>| {
+            let mut encoded = Vec::new();
+            complete(
+                SlotMap::<DefaultKey, u32> {
+                    slots,
+                    free,
+                    key: PhantomData,
+                }
+                .write_value(&mut encoded),
+            )
+            .unwrap();
+
+            complete(SlotMap::<DefaultKey, u32>::read_value(
+                &mut encoded.as_slice(),
+            ))
+        };
+
+        let empty = (NonZeroU32::MIN, None);
+        let occupied = (NonZeroU32::MIN, Some(1));
+
+        let slot_map = read(vec![empty, occupied], vec![0]).unwrap();
+        assert_eq!(slot_map.free, [0]);
+
+        for (slots, free) in [
+            (vec![empty, empty], vec![0, 0]),
+            (vec![empty, occupied], vec![1]),
+            (vec![empty], vec![1]),
+        ] {
+            assert!(matches!(
+                read(slots, free),
+                Err(err) if err.kind() == io::ErrorKind::InvalidData,
+            ));
         }
     }
 }
@@ -4,11 +4,15 @@ use tokio::io::{AsyncReadExt, AsyncWriteExt};
 
 use crate::io_error;
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum SchemaNode {
     Product(Vec<SchemaNode>),
     Sum(Vec<SchemaNode>),
     List(Box<SchemaNode>),
+    /// Map from keys of the first schema to values of the second one.
+    Map(Box<(SchemaNode, SchemaNode)>),
+    /// Set of unique values.
+    Set(Box<SchemaNode>),
     String,
     Boolean,
     Unit,
@@ -45,6 +49,8 @@ pub mod schema_discriminant {
     pub const INT128: u8 = 15;
     pub const FLOAT32: u8 = 16;
     pub const FLOAT64: u8 = 17;
+    pub const MAP: u8 = 18;
+    pub const SET: u8 = 19;
 }
 
 impl SchemaNode {
@@ -53,6 +59,8 @@ impl SchemaNode {
             Self::Product(_) => schema_discriminant::PRODUCT,
             Self::Sum(_) => schema_discriminant::SUM,
             Self::List(_) => schema_discriminant::LIST,
+            Self::Map(_) => schema_discriminant::MAP,
+            Self::Set(_) => schema_discriminant::SET,
             Self::String => schema_discriminant::STRING,
             Self::Boolean => schema_discriminant::BOOLEAN,
             Self::Unit => schema_discriminant::UNIT,
@@ -92,7 +100,7 @@ impl SchemaNode {
                 })?;
 
                 for _ in 0..length {
-                    fields.push(Box::pin(SchemaNode::read(read)).await.unwrap());
+                    fields.push(Box::pin(SchemaNode::read(read)).await?);
                 }
 
                 SchemaNode::Product(fields)
@@ -114,7 +122,7 @@ impl SchemaNode {
                 })?;
 
                 for _ in 0..length {
-                    variants.push(Box::pin(SchemaNode::read(read)).await.unwrap());
+                    variants.push(Box::pin(SchemaNode::read(read)).await?);
                 }
 
                 SchemaNode::Sum(variants)
@@ -135,6 +143,11 @@ impl SchemaNode {
             schema_discriminant::INT128 => Self::Int128,
             schema_discriminant::FLOAT32 => Self::Float32,
             schema_discriminant::FLOAT64 => Self::Float64,
+            schema_discriminant::MAP => Self::Map(Box::new((
+                Box::pin(Self::read(read)).await?,
+                Box::pin(Self::read(read)).await?,
+            ))),
+            schema_discriminant::SET => Self::Set(Box::new(Box::pin(Self::read(read)).await?)),
             _ => {
                 return Err(io_error!(
                     InvalidData,
@@ -180,7 +193,13 @@ impl SchemaNode {
                     Box::pin(variant.write(write)).await?;
                 }
             }
-            Self::List(schema_node) => Box::pin(schema_node.write(write)).await?,
+            Self::List(schema_node) | Self::Set(schema_node) => {
+                Box::pin(schema_node.write(write)).await?
+            }
+            Self::Map(entry) => {
+                Box::pin(entry.0.write(write)).await?;
+                Box::pin(entry.1.write(write)).await?;
+            }
             Self::String
             | Self::Boolean
             | Self::Unit
@@ -1,15 +1,19 @@
 use std::{
     convert::Infallible,
     io,
+    path::Path,
     sync::{Arc, Mutex},
+    time::Duration,
 };
 
 use tokio::{
     io::{AsyncReadExt, AsyncWriteExt},
     net::{TcpListener, ToSocketAddrs},
+    sync::Mutex as AsyncMutex,
+    time::{interval, MissedTickBehavior},
 };
 
-use crate::{io_error, ExpressionNode, SchemaNode, Value};
+use crate::{complete, io_error, ExpressionNode, LogEntry, SchemaNode, Storage, Value};
 
 pub mod request_discriminant {
     pub const GET_SCHEMA: u8 = 0;
@@ -17,9 +21,25 @@ pub mod request_discriminant {
     pub const QUERY: u8 = 2;
 }
 
+pub mod response_discriminant {
+    pub const VALUE: u8 = 0;
+    pub const ERROR: u8 = 1;
+}
+
+/// A database server.
+///
+/// Cloning a server is cheap and gives another handle to the same database.
+///
+/// Requests from every connection are executed one at a time: a request never
+/// observes a partially applied mutation from another one, and the mutations
+/// of concurrent requests are applied in the order the requests are received.
+#[derive(Clone)]
 pub struct Server {
     schema: Arc<Mutex<SchemaNode>>,
     value: Arc<Mutex<Value>>,
+    storage: Option<Arc<Storage>>,
+    /// Held for the whole execution of a request, see [`Server`].
+    execution: Arc<AsyncMutex<()>>,
 }
 
 impl Server {
@@ -27,6 +47,86 @@ impl Server {
         Self {
             schema: Arc::new(Mutex::new(schema)),
             value: Arc::new(Mutex::new(value)),
+            storage: None,
+            execution: Arc::new(AsyncMutex::new(())),
+        }
+    }
+
+    /// Open a server persisted in `directory`.
+    ///
+    /// The directory is created if it doesn't exist, the database then starts
+    /// empty. Otherwise the last snapshot saved with [`Server::snapshot`] is
+    /// loaded and the mutating requests received after it are replayed.
+    pub async fn open(directory: impl AsRef<Path>) -> io::Result<Self> {
+        let (storage, snapshot, log) = Storage::open(directory).await?;
+
+        let (schema, value) = snapshot.unwrap_or((SchemaNode::Unit, Value::Unit));
+
+        let server = Self {
+            schema: Arc::new(Mutex::new(schema)),
+            value: Arc::new(Mutex::new(value)),
+            storage: Some(Arc::new(storage)),
+            execution: Arc::new(AsyncMutex::new(())),
+        };
+
+        for entry in log {
+            match entry {
+                LogEntry::Set(schema, value) => {
+                    *server.schema.lock().unwrap() = schema;
+                    *server.value.lock().unwrap() = value;
+                }
+                LogEntry::Query(expression) => {
+                    // A query that fails now failed the same way when it was first
+                    // received, the mutations it applied before failing are kept.
+                    let _ = expression.evaluate(vec![server.value.clone()]);
+                }
+            }
+        }
+
+        Ok(server)
+    }
+
+    /// Save the schema and value of the database to the directory the server
+    /// was opened with.
+    ///
+    /// This also compacts the write-ahead log, the requests it contains are
+    /// part of the new snapshot so a new empty log is started.
+    pub async fn snapshot(&self) -> io::Result<()> {
+        let Some(storage) = &self.storage else {
+            return Err(io_error!(
+                Unsupported,
+                "cannot snapshot a server that wasn't opened from a directory",
+            ));
+        };
+
+        let _execution = self.execution.lock().await;
+
+        storage
+            .write_snapshot(|| {
+                let mut snapshot = Vec::new();
+
+                let schema = self.schema.lock().unwrap();
+                let value = self.value.lock().unwrap();
+                complete(schema.write(&mut snapshot))?;
+                complete(value.write(&mut snapshot))?;
+
+                Ok(snapshot)
+            })
+            .await
+    }
+
+    /// Call [`Server::snapshot`] every `period`, this never returns unless a
+    /// snapshot fails.
+    pub async fn snapshot_every(&self, period: Duration) -> io::Result<Infallible> {
+        let mut interval = interval(period);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        // The first tick completes immediately, there is nothing new to save yet.
+        interval.tick().await;
+
+        loop {
+            interval.tick().await;
+            self.snapshot().await?;
         }
     }
 
@@ -39,9 +139,12 @@ impl Server {
             let (tcp, address) = listener.accept().await?;
             println!("({address}) connection accepted");
 
-            if let Err(err) = self.listen(tcp).await {
-                println!("({address}) connection closed, error: {err}");
-            }
+            let server = self.clone();
+            tokio::spawn(async move {
+                if let Err(err) = server.listen(tcp).await {
+                    println!("({address}) connection closed, error: {err}");
+                }
+            });
         }
     }
 
@@ -52,24 +155,69 @@ impl Server {
         loop {
             match stream.read_u8().await {
                 Ok(request_discriminant::GET_SCHEMA) => {
-                    self.schema.lock().unwrap().write(&mut stream).await?
+                    let schema = {
+                        let _execution = self.execution.lock().await;
+                        self.schema.lock().unwrap().clone()
+                    };
+                    schema.write(&mut stream).await?;
                 }
                 Ok(request_discriminant::SET) => {
                     let schema = SchemaNode::read(&mut stream).await?;
                     let value = Value::read(&schema, &mut stream).await?;
 
+                    let _execution = self.execution.lock().await;
+
+                    if let Some(storage) = &self.storage {
+                        let mut entry = vec![request_discriminant::SET];
+                        complete(schema.write(&mut entry))?;
+                        complete(value.write(&mut entry))?;
+
+                        storage.append(&entry).await?;
+                    }
+
                     *self.schema.lock().unwrap() = schema;
                     *self.value.lock().unwrap() = value;
                 }
                 Ok(request_discriminant::QUERY) => {
                     dbg!("=========================================");
-                    ExpressionNode::read(&mut stream)
-                        .await?
-                        .evaluate(vec![self.value.clone()])
-                        .lock()
-                        .unwrap()
-                        .write(&mut stream)
-                        .await?;
+                    let expression = ExpressionNode::read(&mut stream).await?;
+
+                    let execution = self.execution.lock().await;
+
+                    let type_check = expression.type_check(&self.schema.lock().unwrap());
+
+                    let result = match type_check {
+                        Ok(()) => {
+                            if let Some(storage) = &self.storage {
+                                if expression.is_mutating() {
+                                    let mut entry = vec![request_discriminant::QUERY];
+                                    complete(expression.write(&mut entry))?;
+
+                                    storage.append(&entry).await?;
+                                }
+                            }
+
+                            expression.evaluate(vec![self.value.clone()])
+                        }
+                        Err(err) => Err(err),
+                    };
+
+                    // The result is serialized before being sent so that no lock
+                    // is held while waiting on the stream.
+                    let mut response = Vec::new();
+                    match result {
+                        Ok(value) => {
+                            response.push(response_discriminant::VALUE);
+                            complete(value.lock().unwrap().write(&mut response))?;
+                        }
+                        Err(err) => {
+                            response.push(response_discriminant::ERROR);
+                            complete(err.write(&mut response))?;
+                        }
+                    }
+                    drop(execution);
+
+                    stream.write_all(&response).await?;
                 }
                 Ok(_) => return Err(io_error!(InvalidData, "invalid discriminant for request")),
                 Err(err) if err.kind() == std::io::ErrorKind::UnexpectedEof => break Ok(()),
@@ -78,3 +226,64 @@ impl Server {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{query_error_discriminant, QueryError};
+
+    /// Send a query and return the discriminant of its response, the payload
+    /// of the response is left to be read from `stream`.
+    async fn query(
+        stream: &mut (impl AsyncReadExt + AsyncWriteExt + Unpin),
+        expression: ExpressionNode,
+    ) -> u8 {
+        stream.write_u8(request_discriminant::QUERY).await.unwrap();
+        expression.write(stream).await.unwrap();
+
+        stream.read_u8().await.unwrap()
+    }
+
+    fn uint32(value: u32) -> ExpressionNode {
+        ExpressionNode::Value(SchemaNode::Uint32, Value::Uint32(value))
+    }
+
+    #[tokio::test]
+    async fn failed_query_responds_with_error() {
+        let (server_stream, mut client_stream) = tokio::io::duplex(64);
+        let server = Server::new(SchemaNode::Uint32, Value::Uint32(0));
+
+        let listen = tokio::spawn(async move { server.listen(server_stream).await });
+
+        let division = ExpressionNode::Div(Box::new((uint32(1), uint32(0))));
+        assert_eq!(
+            query(&mut client_stream, division).await,
+            response_discriminant::ERROR,
+        );
+        assert_eq!(
+            client_stream.read_u8().await.unwrap(),
+            query_error_discriminant::DIVISION_BY_ZERO,
+        );
+
+        // The response is followed by nothing, the next request is served.
+        let path = ExpressionNode::Path(vec![0, 3]);
+        assert_eq!(
+            query(&mut client_stream, path).await,
+            response_discriminant::ERROR,
+        );
+        assert_eq!(
+            QueryError::read(&mut client_stream).await.unwrap(),
+            QueryError::InvalidPath(vec![0, 3]),
+        );
+
+        let addition = ExpressionNode::Add(Box::new((uint32(1), uint32(2))));
+        assert_eq!(
+            query(&mut client_stream, addition).await,
+            response_discriminant::VALUE,
+        );
+        assert_eq!(client_stream.read_u32().await.unwrap(), 3);
+
+        drop(client_stream);
+        listen.await.unwrap().unwrap();
+    }
+}
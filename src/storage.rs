@@ -0,0 +1,341 @@
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+
+use tokio::{
+    fs::{self, File, OpenOptions},
+    io::{AsyncReadExt, AsyncWriteExt, BufReader},
+    sync::Mutex,
+};
+
+use crate::{io_error, request_discriminant, ExpressionNode, SchemaNode, Value};
+
+const SNAPSHOT_FILE_NAME: &str = "snapshot";
+const SNAPSHOT_TEMPORARY_FILE_NAME: &str = "snapshot.tmp";
+const LOG_FILE_PREFIX: &str = "log.";
+
+/// Directory in which a [`Server`](crate::Server) persists its database.
+///
+/// The directory contains a snapshot and a write-ahead log:
+/// - The snapshot is the sequence number of its log as a 64 bit unsigned
+///   integer followed by the [`SchemaNode`] and [`Value`] of the database,
+///   using the same encoding as the protocol.
+/// - The log is named after its sequence number (`log.<sequence>`) and holds
+///   every mutating request received since the snapshot was taken, each one is
+///   a request discriminant followed by the payload of the request.
+///
+/// Each snapshot starts a new log, a crash between the write of a snapshot and
+/// the creation of the next log can therefore never replay a request twice.
+pub(crate) struct Storage {
+    directory: PathBuf,
+    log: Mutex<Log>,
+}
+
+struct Log {
+    sequence: u64,
+    file: File,
+}
+
+pub(crate) enum LogEntry {
+    Set(SchemaNode, Value),
+    Query(ExpressionNode),
+}
+
+impl Storage {
+    /// Open the storage in `directory`, returning the database as it was
+    /// when the last snapshot was taken and the log entries to replay on top of it.
+    pub async fn open(
+        directory: impl AsRef<Path>,
+    ) -> io::Result<(Self, Option<(SchemaNode, Value)>, Vec<LogEntry>)> {
+        let directory = directory.as_ref().to_path_buf();
+
+        fs::create_dir_all(&directory).await?;
+
+        let (sequence, snapshot) = match File::open(directory.join(SNAPSHOT_FILE_NAME)).await {
+            Ok(file) => {
+                let mut read = BufReader::new(file);
+
+                let sequence = read.read_u64().await?;
+                let schema = SchemaNode::read(&mut read).await?;
+                let value = Value::read(&schema, &mut read).await?;
+
+                (sequence, Some((schema, value)))
+            }
+            Err(err) if err.kind() == io::ErrorKind::NotFound => (0, None),
+            Err(err) => return Err(err),
+        };
+
+        let log_path = directory.join(format!("{LOG_FILE_PREFIX}{sequence}"));
+
+        let (entries, valid_length) = match fs::read(&log_path).await {
+            Ok(log) => read_log(&log).await?,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => (Vec::new(), 0),
+            Err(err) => return Err(err),
+        };
+
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&log_path)
+            .await?;
+
+        // Drop a partially written entry left by a crash so new entries are
+        // appended after the last complete one.
+        file.set_len(valid_length).await?;
+
+        remove_stale_logs(&directory, sequence).await?;
+
+        Ok((
+            Self {
+                directory,
+                log: Mutex::new(Log { sequence, file }),
+            },
+            snapshot,
+            entries,
+        ))
+    }
+
+    /// Durably append a request to the log.
+    pub async fn append(&self, entry: &[u8]) -> io::Result<()> {
+        let mut log = self.log.lock().await;
+
+        log.file.write_all(entry).await?;
+        log.file.sync_data().await
+    }
+
+    /// Replace the current snapshot with the one encoded by `snapshot` and start
+    /// a new empty log, the entries of the previous log being part of the snapshot.
+    ///
+    /// `snapshot` is called while the log is locked so that no request can be
+    /// appended between the encoding of the snapshot and the start of the new log.
+    pub async fn write_snapshot(
+        &self,
+        snapshot: impl FnOnce() -> io::Result<Vec<u8>>,
+    ) -> io::Result<()> {
+        let mut log = self.log.lock().await;
+
+        let sequence = log.sequence.checked_add(1).ok_or(io_error!(
+            Other,
+            "log sequence number doesn't fit into a 64 bit unsigned integer",
+        ))?;
+
+        let temporary_path = self.directory.join(SNAPSHOT_TEMPORARY_FILE_NAME);
+
+        // The snapshot is first written to a temporary file which is then renamed,
+        // so a crash in the middle of the write leaves the previous snapshot intact.
+        let mut file = File::create(&temporary_path).await?;
+        file.write_u64(sequence).await?;
+        file.write_all(&snapshot()?).await?;
+        file.sync_all().await?;
+        drop(file);
+
+        fs::rename(temporary_path, self.directory.join(SNAPSHOT_FILE_NAME)).await?;
+
+        log.file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.directory.join(format!("{LOG_FILE_PREFIX}{sequence}")))
+            .await?;
+        log.sequence = sequence;
+
+        // The rename and the new log are only durable once the directory is
+        // synced, the previous log is needed by the previous snapshot until then.
+        File::open(&self.directory).await?.sync_all().await?;
+
+        remove_stale_logs(&self.directory, sequence).await
+    }
+}
+
+impl LogEntry {
+    async fn read(read: &mut (impl AsyncReadExt + Unpin)) -> io::Result<Self> {
+        match read.read_u8().await? {
+            request_discriminant::SET => {
+                let schema = SchemaNode::read(read).await?;
+                let value = Value::read(&schema, read).await?;
+
+                Ok(Self::Set(schema, value))
+            }
+            request_discriminant::QUERY => Ok(Self::Query(ExpressionNode::read(read).await?)),
+            _ => Err(io_error!(InvalidData, "invalid discriminant for log entry")),
+        }
+    }
+}
+
+/// Parse the entries of a log, returning them with the length of the log
+/// without its trailing partially written entry, if any.
+async fn read_log(mut log: &[u8]) -> io::Result<(Vec<LogEntry>, u64)> {
+    let length = log.len();
+    let mut entries = Vec::new();
+    let mut valid_length = 0;
+
+    while !log.is_empty() {
+        match LogEntry::read(&mut log).await {
+            Ok(entry) => {
+                entries.push(entry);
+                valid_length = length - log.len();
+            }
+            Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => break,
+            Err(err) => return Err(err),
+        }
+    }
+
+    Ok((entries, valid_length as u64))
+}
+
+async fn remove_stale_logs(directory: &Path, sequence: u64) -> io::Result<()> {
+    let mut entries = fs::read_dir(directory).await?;
+
+    while let Some(entry) = entries.next_entry().await? {
+        let Some(log_sequence) = entry
+            .file_name()
+            .to_str()
+            .and_then(|name| name.strip_prefix(LOG_FILE_PREFIX))
+            .and_then(|log_sequence| log_sequence.parse::<u64>().ok())
+        else {
+            continue;
+        };
+
+        if log_sequence < sequence {
+            fs::remove_file(entry.path()).await?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::complete;
+
+    /// Encoding of a `u32` database, as written in a snapshot or a log entry.
+    fn database(value: u32) -> Vec<u8> {
+        let mut database = Vec::new();
+        complete(SchemaNode::Uint32.write(&mut database)).unwrap();
+        complete(Value::Uint32(value).write(&mut database)).unwrap();
+
+        database
+    }
+
+    fn set_entry(value: u32) -> Vec<u8> {
+        [vec![request_discriminant::SET], database(value)].concat()
+    }
+
+    fn uint32_database(database: &(SchemaNode, Value)) -> u32 {
+        match database {
+            (SchemaNode::Uint32, Value::Uint32(value)) => *value,
+            _ => panic!("database isn't a u32"),
+        }
+    }
+
+    /// Values set by log entries, all of which must be set entries.
+    fn set_values(entries: Vec<LogEntry>) -> Vec<u32> {
+        entries
+            .into_iter()
+            .map(|entry| match entry {
+                LogEntry::Set(schema, value) => uint32_database(&(schema, value)),
+                LogEntry::Query(_) => panic!("log entry isn't a set entry"),
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn snapshot_is_loaded_on_open() {
+        let directory = tempfile::tempdir().unwrap();
+
+        let (storage, snapshot, _) = Storage::open(directory.path()).await.unwrap();
+        assert!(snapshot.is_none());
+
+        storage.write_snapshot(|| Ok(database(7))).await.unwrap();
+        drop(storage);
+
+        let (_, snapshot, entries) = Storage::open(directory.path()).await.unwrap();
+        assert_eq!(uint32_database(&snapshot.unwrap()), 7);
+        assert!(entries.is_empty());
+    }
+
+    #[tokio::test]
+    async fn log_is_replayed_on_open() {
+        let directory = tempfile::tempdir().unwrap();
+
+        let (storage, _, entries) = Storage::open(directory.path()).await.unwrap();
+        assert!(entries.is_empty());
+
+        let mut query_entry = vec![request_discriminant::QUERY];
+        complete(ExpressionNode::Path(vec![0, 1]).write(&mut query_entry)).unwrap();
+
+        storage.append(&set_entry(1)).await.unwrap();
+        storage.append(&query_entry).await.unwrap();
+        storage.append(&set_entry(2)).await.unwrap();
+        drop(storage);
+
+        let (_, snapshot, mut entries) = Storage::open(directory.path()).await.unwrap();
+        assert!(snapshot.is_none());
+        assert_eq!(entries.len(), 3);
+
+        let LogEntry::Query(ExpressionNode::Path(path)) = entries.remove(1) else {
+            panic!("second log entry isn't the query");
+        };
+        assert_eq!(path, [0, 1]);
+        assert_eq!(set_values(entries), [1, 2]);
+    }
+
+    #[tokio::test]
+    async fn partially_written_entry_is_dropped() {
+        let directory = tempfile::tempdir().unwrap();
+
+        let (storage, _, _) = Storage::open(directory.path()).await.unwrap();
+
+        let partial_entry = set_entry(2);
+        storage.append(&set_entry(1)).await.unwrap();
+        storage
+            .append(&partial_entry[..partial_entry.len() - 1])
+            .await
+            .unwrap();
+        drop(storage);
+
+        let (storage, _, entries) = Storage::open(directory.path()).await.unwrap();
+        assert_eq!(set_values(entries), [1]);
+        assert_eq!(
+            fs::metadata(directory.path().join("log.0"))
+                .await
+                .unwrap()
+                .len(),
+            set_entry(1).len() as u64,
+        );
+
+        // New entries follow the last complete one.
+        storage.append(&set_entry(3)).await.unwrap();
+        drop(storage);
+
+        let (_, _, entries) = Storage::open(directory.path()).await.unwrap();
+        assert_eq!(set_values(entries), [1, 3]);
+    }
+
+    #[tokio::test]
+    async fn snapshot_removes_stale_logs() {
+        let directory = tempfile::tempdir().unwrap();
+
+        let (storage, _, _) = Storage::open(directory.path()).await.unwrap();
+        storage.append(&set_entry(1)).await.unwrap();
+
+        storage.write_snapshot(|| Ok(database(1))).await.unwrap();
+        assert!(!directory.path().join("log.0").exists());
+        assert!(directory.path().join("log.1").exists());
+
+        storage.append(&set_entry(2)).await.unwrap();
+        drop(storage);
+
+        // A log left behind by a crash before its removal isn't replayed and
+        // is removed on open.
+        fs::write(directory.path().join("log.0"), set_entry(3))
+            .await
+            .unwrap();
+
+        let (_, snapshot, entries) = Storage::open(directory.path()).await.unwrap();
+        assert_eq!(uint32_database(&snapshot.unwrap()), 1);
+        assert_eq!(set_values(entries), [2]);
+        assert!(!directory.path().join("log.0").exists());
+    }
+}
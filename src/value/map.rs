@@ -0,0 +1,90 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use crate::{complete, Value};
+
+/// Encoded key, key and value of an entry.
+type Entry = (Vec<u8>, Arc<Mutex<Value>>, Arc<Mutex<Value>>);
+
+/// Storage of a [`Value::Map`], entries are indexed by the encoding of their key.
+///
+/// The entries are kept in insertion order, and removing one moves the last
+/// entry in its place. The order of the entries therefore only depends on the
+/// operations applied to the map, so replaying the same requests on a copy of
+/// the map iterates it in the same order.
+#[derive(Clone, Default)]
+pub struct ValueMap {
+    entries: Vec<Entry>,
+    indices: HashMap<Vec<u8>, usize>,
+}
+
+impl ValueMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn get(&self, key: &Value) -> Option<&Arc<Mutex<Value>>> {
+        self.indices
+            .get(&encode(key))
+            .map(|index| &self.entries[*index].2)
+    }
+
+    pub fn contains_key(&self, key: &Value) -> bool {
+        self.indices.contains_key(&encode(key))
+    }
+
+    /// Insert an entry, returning the previous value of `key` if there was one.
+    pub fn insert(&mut self, key: Value, value: Arc<Mutex<Value>>) -> Option<Arc<Mutex<Value>>> {
+        let encoded_key = encode(&key);
+
+        if let Some(index) = self.indices.get(&encoded_key) {
+            return Some(std::mem::replace(&mut self.entries[*index].2, value));
+        }
+
+        self.indices.insert(encoded_key.clone(), self.entries.len());
+        self.entries
+            .push((encoded_key, Arc::new(Mutex::new(key)), value));
+
+        None
+    }
+
+    /// Remove the entry of `key`, returning its value if there was one.
+    pub fn remove(&mut self, key: &Value) -> Option<Arc<Mutex<Value>>> {
+        let index = self.indices.remove(&encode(key))?;
+        let (_, _, value) = self.entries.swap_remove(index);
+
+        if let Some((moved_key, _, _)) = self.entries.get(index) {
+            self.indices.insert(moved_key.clone(), index);
+        }
+
+        Some(value)
+    }
+
+    /// Iterate over the keys and values of the map.
+    ///
+    /// Keys must not be modified through the returned references as the map
+    /// is indexed by them.
+    pub fn iter(&self) -> impl Iterator<Item = (&Arc<Mutex<Value>>, &Arc<Mutex<Value>>)> {
+        self.entries.iter().map(|(_, key, value)| (key, value))
+    }
+}
+
+/// Encoding of a key, equal keys of the same schema have the same encoding.
+pub(super) fn encode(key: &Value) -> Vec<u8> {
+    let mut encoded_key = Vec::new();
+
+    complete(key.write(&mut encoded_key))
+        .expect("a key too large to be encoded can't be read in the first place");
+
+    encoded_key
+}
@@ -1,4 +1,5 @@
 use std::{
+    cmp::Ordering,
     fmt::{self, Debug},
     io,
     sync::{Arc, Mutex, TryLockError},
@@ -8,11 +9,18 @@ use tokio::io::{AsyncReadExt, AsyncWriteExt};
 
 use crate::{io_error, SchemaNode};
 
+mod map;
+mod set;
+
+pub use self::{map::ValueMap, set::ValueSet};
+
 #[derive(Clone)]
 pub enum Value {
     Product(Vec<Arc<Mutex<Value>>>),
     Sum(u32, Arc<Mutex<Value>>),
     List(Vec<Arc<Mutex<Value>>>),
+    Map(ValueMap),
+    Set(ValueSet),
     String(String),
     Boolean(bool),
     Unit,
@@ -56,7 +64,9 @@ impl Value {
             Self::List(list) => list
                 .get(usize::try_from(*segment).ok()?)
                 .and_then(|value| Self::scope(value.clone(), segments)),
-            Self::String(_)
+            Self::Map(_)
+            | Self::Set(_)
+            | Self::String(_)
             | Self::Boolean(_)
             | Self::Unit
             | Self::Uint8(_)
@@ -74,25 +84,102 @@ impl Value {
         }
     }
 
-    pub fn equal(&self, rhs: &Self) -> bool {
-        match (self, rhs) {
+    /// Copy of the value that doesn't share any child with it, unlike [`Clone`]
+    /// which copies the value but shares its children.
+    pub fn deep_clone(&self) -> Self {
+        match self {
+            Self::Product(fields) => {
+                Self::Product(fields.iter().map(Self::deep_clone_shared).collect())
+            }
+            Self::Sum(discriminant, variant) => {
+                Self::Sum(*discriminant, Self::deep_clone_shared(variant))
+            }
+            Self::List(elements) => {
+                Self::List(elements.iter().map(Self::deep_clone_shared).collect())
+            }
+            Self::Map(entries) => {
+                let mut map = ValueMap::new();
+
+                for (key, value) in entries.iter() {
+                    map.insert(
+                        key.lock().unwrap().deep_clone(),
+                        Self::deep_clone_shared(value),
+                    );
+                }
+
+                Self::Map(map)
+            }
+            Self::Set(elements) => {
+                let mut set = ValueSet::new();
+
+                for element in elements.iter() {
+                    set.insert(element.lock().unwrap().deep_clone());
+                }
+
+                Self::Set(set)
+            }
+            _ => self.clone(),
+        }
+    }
+
+    fn deep_clone_shared(value: &Arc<Mutex<Self>>) -> Arc<Mutex<Self>> {
+        Arc::new(Mutex::new(value.lock().unwrap().deep_clone()))
+    }
+
+    /// Structural equality of two values, `None` if the values don't have the same shape.
+    pub fn equal(&self, rhs: &Self) -> Option<bool> {
+        Some(match (self, rhs) {
             (Self::Product(lhs), Self::Product(rhs)) => {
-                debug_assert_eq!(lhs.len(), rhs.len());
+                if lhs.len() != rhs.len() {
+                    return None;
+                }
+
+                for (lhs, rhs) in lhs.iter().zip(rhs) {
+                    if !Self::equal_shared(lhs, rhs)? {
+                        return Some(false);
+                    }
+                }
 
-                lhs.iter()
-                    .zip(rhs)
-                    .all(|(lhs, rhs)| lhs.lock().unwrap().equal(&rhs.lock().unwrap()))
+                true
             }
             (Self::Sum(lhs_discriminant, lhs), Self::Sum(rhs_discriminant, rhs)) => {
-                (lhs_discriminant == rhs_discriminant)
-                    && lhs.lock().unwrap().equal(&rhs.lock().unwrap())
+                (lhs_discriminant == rhs_discriminant) && Self::equal_shared(lhs, rhs)?
             }
             (Self::List(lhs), Self::List(rhs)) => {
+                if lhs.len() != rhs.len() {
+                    return Some(false);
+                }
+
+                for (lhs, rhs) in lhs.iter().zip(rhs) {
+                    if !Self::equal_shared(lhs, rhs)? {
+                        return Some(false);
+                    }
+                }
+
+                true
+            }
+            (Self::Map(lhs), Self::Map(rhs)) => {
+                if lhs.len() != rhs.len() {
+                    return Some(false);
+                }
+
+                for (key, lhs) in lhs.iter() {
+                    let Some(rhs) = rhs.get(&key.lock().unwrap()) else {
+                        return Some(false);
+                    };
+
+                    if !Self::equal_shared(lhs, rhs)? {
+                        return Some(false);
+                    }
+                }
+
+                true
+            }
+            (Self::Set(lhs), Self::Set(rhs)) => {
                 lhs.len() == rhs.len()
                     && lhs
                         .iter()
-                        .zip(rhs)
-                        .all(|(lhs, rhs)| lhs.lock().unwrap().equal(&rhs.lock().unwrap()))
+                        .all(|element| rhs.contains(&element.lock().unwrap()))
             }
             (Self::String(lhs), Self::String(rhs)) => lhs == rhs,
             (Self::Uint8(lhs), Self::Uint8(rhs)) => lhs == rhs,
@@ -109,8 +196,115 @@ impl Value {
             (Self::Float64(lhs), Self::Float64(rhs)) => lhs == rhs,
             (Self::Boolean(lhs), Self::Boolean(rhs)) => lhs == rhs,
             (Self::Unit, Self::Unit) => true,
-            a => panic!("{a:#?}"),
+            _ => return None,
+        })
+    }
+
+    /// Same as [`Value::equal`] for values behind locks, which may be the same lock.
+    pub fn equal_shared(lhs: &Arc<Mutex<Self>>, rhs: &Arc<Mutex<Self>>) -> Option<bool> {
+        if Arc::ptr_eq(lhs, rhs) {
+            let value = lhs.lock().unwrap().clone();
+            return value.equal(&rhs.lock().unwrap());
+        }
+
+        lhs.lock().unwrap().equal(&rhs.lock().unwrap())
+    }
+
+    /// Lexicographic ordering of two values, `None` if the values don't have the
+    /// same shape and `Some(None)` if they are unordered because of a NaN float.
+    pub fn compare(&self, rhs: &Self) -> Option<Option<Ordering>> {
+        self.compare_with(rhs, false)
+    }
+
+    /// Same as [`Value::compare`] but floats are ordered by their `total_cmp`
+    /// method, NaN being greater than any other float, so values of the same
+    /// shape are always ordered.
+    pub fn total_compare(&self, rhs: &Self) -> Option<Ordering> {
+        self.compare_with(rhs, true).map(Option::unwrap)
+    }
+
+    fn compare_with(&self, rhs: &Self, total: bool) -> Option<Option<Ordering>> {
+        Some(match (self, rhs) {
+            (Self::Product(lhs), Self::Product(rhs)) => {
+                if lhs.len() != rhs.len() {
+                    return None;
+                }
+
+                for (lhs, rhs) in lhs.iter().zip(rhs) {
+                    match Self::compare_shared_with(lhs, rhs, total)? {
+                        Some(Ordering::Equal) => {}
+                        ordering => return Some(ordering),
+                    }
+                }
+
+                Some(Ordering::Equal)
+            }
+            (Self::Sum(lhs_discriminant, lhs), Self::Sum(rhs_discriminant, rhs)) => {
+                match lhs_discriminant.cmp(rhs_discriminant) {
+                    Ordering::Equal => Self::compare_shared_with(lhs, rhs, total)?,
+                    ordering => Some(ordering),
+                }
+            }
+            (Self::List(lhs), Self::List(rhs)) => {
+                for (lhs, rhs) in lhs.iter().zip(rhs) {
+                    match Self::compare_shared_with(lhs, rhs, total)? {
+                        Some(Ordering::Equal) => {}
+                        ordering => return Some(ordering),
+                    }
+                }
+
+                Some(lhs.len().cmp(&rhs.len()))
+            }
+            (Self::String(lhs), Self::String(rhs)) => lhs.partial_cmp(rhs),
+            (Self::Uint8(lhs), Self::Uint8(rhs)) => lhs.partial_cmp(rhs),
+            (Self::Uint16(lhs), Self::Uint16(rhs)) => lhs.partial_cmp(rhs),
+            (Self::Uint32(lhs), Self::Uint32(rhs)) => lhs.partial_cmp(rhs),
+            (Self::Uint64(lhs), Self::Uint64(rhs)) => lhs.partial_cmp(rhs),
+            (Self::Uint128(lhs), Self::Uint128(rhs)) => lhs.partial_cmp(rhs),
+            (Self::Int8(lhs), Self::Int8(rhs)) => lhs.partial_cmp(rhs),
+            (Self::Int16(lhs), Self::Int16(rhs)) => lhs.partial_cmp(rhs),
+            (Self::Int32(lhs), Self::Int32(rhs)) => lhs.partial_cmp(rhs),
+            (Self::Int64(lhs), Self::Int64(rhs)) => lhs.partial_cmp(rhs),
+            (Self::Int128(lhs), Self::Int128(rhs)) => lhs.partial_cmp(rhs),
+            (Self::Float32(lhs), Self::Float32(rhs)) if total => Some(lhs.total_cmp(rhs)),
+            (Self::Float64(lhs), Self::Float64(rhs)) if total => Some(lhs.total_cmp(rhs)),
+            (Self::Float32(lhs), Self::Float32(rhs)) => lhs.partial_cmp(rhs),
+            (Self::Float64(lhs), Self::Float64(rhs)) => lhs.partial_cmp(rhs),
+            (Self::Boolean(lhs), Self::Boolean(rhs)) => lhs.partial_cmp(rhs),
+            (Self::Unit, Self::Unit) => Some(Ordering::Equal),
+            _ => return None,
+        })
+    }
+
+    /// Same as [`Value::compare`] for values behind locks, which may be the same lock.
+    pub fn compare_shared(
+        lhs: &Arc<Mutex<Self>>,
+        rhs: &Arc<Mutex<Self>>,
+    ) -> Option<Option<Ordering>> {
+        Self::compare_shared_with(lhs, rhs, false)
+    }
+
+    /// Same as [`Value::total_compare`] for values behind locks, which may be the same lock.
+    pub fn total_compare_shared(
+        lhs: &Arc<Mutex<Self>>,
+        rhs: &Arc<Mutex<Self>>,
+    ) -> Option<Ordering> {
+        Self::compare_shared_with(lhs, rhs, true).map(Option::unwrap)
+    }
+
+    fn compare_shared_with(
+        lhs: &Arc<Mutex<Self>>,
+        rhs: &Arc<Mutex<Self>>,
+        total: bool,
+    ) -> Option<Option<Ordering>> {
+        if Arc::ptr_eq(lhs, rhs) {
+            let value = lhs.lock().unwrap().clone();
+            return value.compare_with(&rhs.lock().unwrap(), total);
         }
+
+        lhs.lock()
+            .unwrap()
+            .compare_with(&rhs.lock().unwrap(), total)
     }
 
     pub async fn read(
@@ -176,6 +370,37 @@ impl Value {
 
                 Self::List(values)
             }
+            SchemaNode::Map(entry) => {
+                let (key_schema, value_schema) = entry.as_ref();
+
+                let length = read.read_u32().await?;
+
+                let mut map = ValueMap::new();
+
+                for _ in 0..length {
+                    let key = Box::pin(Self::read(key_schema, read)).await?;
+                    let value = Box::pin(Self::read(value_schema, read)).await?;
+
+                    if map.insert(key, Arc::new(Mutex::new(value))).is_some() {
+                        return Err(io_error!(InvalidData, "map keys are not unique"));
+                    }
+                }
+
+                Self::Map(map)
+            }
+            SchemaNode::Set(element_schema) => {
+                let length = read.read_u32().await?;
+
+                let mut set = ValueSet::new();
+
+                for _ in 0..length {
+                    if !set.insert(Box::pin(Self::read(element_schema, read)).await?) {
+                        return Err(io_error!(InvalidData, "set elements are not unique"));
+                    }
+                }
+
+                Self::Set(set)
+            }
             SchemaNode::String => {
                 let length: usize = read.read_u32().await?.try_into().map_err(|_| {
                     io_error!(
@@ -238,6 +463,49 @@ impl Value {
                     Box::pin(value.lock().unwrap().write(write)).await?;
                 }
             }
+            Self::Map(map) => {
+                write
+                    .write_u32(map.len().try_into().map_err(|_| {
+                        io_error!(
+                            OutOfMemory,
+                            "map value length doesn't fit into a 32 bit unsigned integer",
+                        )
+                    })?)
+                    .await?;
+
+                // Entries are copied so no lock is held while writing them.
+                let entries = map
+                    .iter()
+                    .map(|(key, value)| {
+                        (key.lock().unwrap().clone(), value.lock().unwrap().clone())
+                    })
+                    .collect::<Vec<_>>();
+
+                for (key, value) in entries {
+                    Box::pin(key.write(write)).await?;
+                    Box::pin(value.write(write)).await?;
+                }
+            }
+            Self::Set(set) => {
+                write
+                    .write_u32(set.len().try_into().map_err(|_| {
+                        io_error!(
+                            OutOfMemory,
+                            "set value length doesn't fit into a 32 bit unsigned integer",
+                        )
+                    })?)
+                    .await?;
+
+                // Elements are copied so no lock is held while writing them.
+                let elements = set
+                    .iter()
+                    .map(|element| element.lock().unwrap().clone())
+                    .collect::<Vec<_>>();
+
+                for element in elements {
+                    Box::pin(element.write(write)).await?;
+                }
+            }
             Self::String(value) => {
                 write
                     .write_u32(value.len().try_into().map_err(|_| {
@@ -317,6 +585,37 @@ impl Debug for Value {
 
                 list.finish()
             }
+            Value::Map(map) => {
+                let mut debug_map = f.debug_map();
+
+                for (key, value) in map.iter() {
+                    match key.try_lock() {
+                        Ok(key) => debug_map.key(&*key),
+                        Err(TryLockError::WouldBlock) => debug_map.key(&LockedDebug),
+                        Err(TryLockError::Poisoned(poison_err)) => debug_map.key(&poison_err),
+                    };
+                    match value.try_lock() {
+                        Ok(value) => debug_map.value(&*value),
+                        Err(TryLockError::WouldBlock) => debug_map.value(&LockedDebug),
+                        Err(TryLockError::Poisoned(poison_err)) => debug_map.value(&poison_err),
+                    };
+                }
+
+                debug_map.finish()
+            }
+            Value::Set(set) => {
+                let mut debug_set = f.debug_set();
+
+                for element in set.iter() {
+                    match element.try_lock() {
+                        Ok(element) => debug_set.entry(&*element),
+                        Err(TryLockError::WouldBlock) => debug_set.entry(&LockedDebug),
+                        Err(TryLockError::Poisoned(poison_err)) => debug_set.entry(&poison_err),
+                    };
+                }
+
+                debug_set.finish()
+            }
             Value::String(value) => Debug::fmt(value, f),
             Value::Boolean(value) => Debug::fmt(value, f),
             Value::Unit => Debug::fmt(&(), f),
@@ -360,6 +659,24 @@ impl Value {
                 })
                 .saturating_add(list.len().saturating_mul(2))
             }),
+            Value::Map(map) => map.iter().fold(0, |len, (key, value)| {
+                len.saturating_add(match key.try_lock() {
+                    Ok(key) => key.estimate_debug_width(),
+                    Err(_) => 8,
+                })
+                .saturating_add(match value.try_lock() {
+                    Ok(value) => value.estimate_debug_width(),
+                    Err(_) => 8,
+                })
+                .saturating_add(map.len().saturating_mul(4))
+            }),
+            Value::Set(set) => set.iter().fold(0, |len, element| {
+                len.saturating_add(match element.try_lock() {
+                    Ok(element) => element.estimate_debug_width(),
+                    Err(_) => 8,
+                })
+                .saturating_add(set.len().saturating_mul(2))
+            }),
             Value::String(string) => string.len() + 2,
             Value::Boolean(true) => 4,
             Value::Boolean(false) => 5,
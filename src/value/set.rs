@@ -0,0 +1,73 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use super::map::encode;
+use crate::Value;
+
+/// Storage of a [`Value::Set`], elements are indexed by their encoding.
+///
+/// Like [`ValueMap`](super::ValueMap), the elements are kept in insertion
+/// order and removing one moves the last element in its place.
+#[derive(Clone, Default)]
+pub struct ValueSet {
+    elements: Vec<(Vec<u8>, Arc<Mutex<Value>>)>,
+    indices: HashMap<Vec<u8>, usize>,
+}
+
+impl ValueSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.elements.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.elements.is_empty()
+    }
+
+    pub fn contains(&self, value: &Value) -> bool {
+        self.indices.contains_key(&encode(value))
+    }
+
+    /// Insert an element, returning whether it wasn't already in the set.
+    pub fn insert(&mut self, value: Value) -> bool {
+        let encoded_value = encode(&value);
+
+        if self.indices.contains_key(&encoded_value) {
+            return false;
+        }
+
+        self.indices
+            .insert(encoded_value.clone(), self.elements.len());
+        self.elements
+            .push((encoded_value, Arc::new(Mutex::new(value))));
+
+        true
+    }
+
+    /// Remove an element, returning whether it was in the set.
+    pub fn remove(&mut self, value: &Value) -> bool {
+        let Some(index) = self.indices.remove(&encode(value)) else {
+            return false;
+        };
+        self.elements.swap_remove(index);
+
+        if let Some((moved_value, _)) = self.elements.get(index) {
+            self.indices.insert(moved_value.clone(), index);
+        }
+
+        true
+    }
+
+    /// Iterate over the elements of the set.
+    ///
+    /// Elements must not be modified through the returned references as the
+    /// set is indexed by them.
+    pub fn iter(&self) -> impl Iterator<Item = &Arc<Mutex<Value>>> {
+        self.elements.iter().map(|(_, value)| value)
+    }
+}